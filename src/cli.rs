@@ -1,6 +1,53 @@
-use chrono::{DateTime, NaiveDate, SecondsFormat, Utc};
+use crate::editor;
+use crate::settings::{self, CellFit, Tone};
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, SecondsFormat, TimeZone, Utc,
+};
 use prettytable::{Cell, Row, Table};
-use std::io::{self, prelude::*};
+use std::cell::{Cell as Flag, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::io::{self, prelude::*, IsTerminal};
+use uuid::Uuid;
+
+/// print a message for people
+///     - in batch mode it goes to stderr, so stdout only carries tables
+macro_rules! note {
+    ($($arg:tt)*) => {
+        $crate::cli::note(&format!($($arg)*))
+    };
+}
+
+/// where prompts are answered from in batch mode, and how many lines were read
+struct Script {
+    lines: Box<dyn BufRead>,
+    line: usize,
+}
+
+/// answers given ahead of time (e.g. as command-line flags), used instead of asking
+#[derive(Default)]
+struct Answers {
+    /// answers to the prompts with these keys
+    named: Vec<(String, String)>,
+    /// answers not yet given a key by `name_positional`
+    positional: VecDeque<String>,
+    /// the answer to whichever prompt comes next
+    next: Option<String>,
+    /// values were given on the command line, so optional prompts are not asked
+    inline: bool,
+    /// fail instead of reading stdin when a prompt has no answer
+    strict: bool,
+    /// strict commands may create unknown names (`--create`)
+    create: bool,
+}
+
+thread_local! {
+    static ANSWERS: RefCell<Answers> = RefCell::new(Answers::default());
+    static FAILED: Flag<bool> = const { Flag::new(false) };
+    static SCRIPT: RefCell<Option<Script>> = const { RefCell::new(None) };
+    static OPEN_MONTH: Flag<Option<(i32, u32)>> = const { Flag::new(None) };
+    static NOTES: RefCell<Option<Vec<String>>> = const { RefCell::new(None) };
+}
 
 // getting the commands
 pub enum Command {
@@ -8,153 +55,1573 @@ pub enum Command {
     Cancel,
     Quit,
     Update,
+    Categorize,
+    Rules,
     Help,
     List(String),
     Add(String),
     Edit(String),
     Delete(String),
+    Merge(String),
+    Bulk(String),
     Search(String),
+    Import(String),
+    Export(String),
+    Duplicates(String),
     RollOver(String),
+    Undo(String),
+    Redo(String),
+    Sort(String),
+    Open(String),
+    Close,
+    Reopen,
+    Tui,
+    Columns(String),
 }
 
+impl Command {
+    /// how the command is written, for the undo history
+    ///     - None for commands that never change the month
+    pub fn label(&self) -> Option<String> {
+        match self {
+            Command::Update => Some(String::from("--update")),
+            Command::Categorize => Some(String::from("--categorize")),
+            Command::Add(args) => Some(format!("a {}", args)),
+            Command::Edit(args) => Some(format!("e {}", args)),
+            Command::Delete(args) => Some(format!("d {}", args)),
+            Command::Merge(args) => Some(format!("m {}", args)),
+            Command::Bulk(args) => Some(format!("b {}", args)),
+            Command::Import(args) => Some(format!("i {}", args)),
+            Command::Duplicates(args) => Some(format!("dups {}", args).trim_end().to_string()),
+            Command::RollOver(args) => Some(format!("--roll {}", args).trim_end().to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// the commands of the prompt, for Tab completion
+pub const COMMANDS: [&str; 25] = [
+    "q",
+    "l",
+    "a",
+    "e",
+    "d",
+    "m",
+    "b",
+    "/",
+    "s",
+    "cols",
+    "i",
+    "x",
+    "u",
+    "r",
+    "dups",
+    "rules",
+    "?",
+    "--update",
+    "--categorize",
+    "--cancel",
+    "--roll",
+    "--open",
+    "--close",
+    "--reopen",
+    "tui",
+];
+
 pub fn prompt() -> Command {
-    let mut inputs: Vec<String> = Vec::new();
-    for word in get_input("$").split_whitespace() {
-        inputs.push(String::from(word));
+    let line = match read_line("$") {
+        Some(line) => line,
+        // end of input (Ctrl-D) saves and quits
+        None => return Command::Quit,
+    };
+    if line.starts_with('#') {
+        return Command::Empty;
     }
-    let command = match inputs.get(0) {
-        Some(st) => st,
-        None => "",
+    let inputs = split_quoted(&line);
+    let command = match inputs.first() {
+        Some((st, _)) => st.clone(),
+        None => String::new(),
     };
-    let types = match inputs.get(1..) {
-        Some(st) => {
-            let mut tmp = String::new();
-            for s in st {
-                tmp.push_str(s);
-                tmp.push(' ');
+    quoted_command(&command, inputs.into_iter().skip(1).collect())
+}
+
+/// split a line into words, keeping "quoted" or 'quoted' text together
+pub fn split_words(line: &str) -> Vec<String> {
+    split_quoted(line)
+        .into_iter()
+        .map(|(word, _)| word)
+        .collect()
+}
+
+/// split a line into words, each with whether it starts with a quote
+///     - `"` opens a quote anywhere, `'` only at the start of a word so `McDonald's` stays a word
+pub fn split_quoted(line: &str) -> Vec<(String, bool)> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quoted = false;
+    let mut quote: Option<char> = None;
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => word.push(c),
+            None if c == '"' || (c == '\'' && !in_word) => {
+                quote = Some(c);
+                quoted |= !in_word;
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    words.push((std::mem::take(&mut word), quoted));
+                    in_word = false;
+                    quoted = false;
+                }
+            }
+            None => {
+                word.push(c);
+                in_word = true;
             }
-            tmp.trim_end().to_string()
         }
-        None => String::new(),
-    };
+    }
+    if in_word {
+        words.push((word, quoted));
+    }
+    words
+}
+
+/// the Command named by command, with words as its arguments
+///     - the long names are what the command-line subcommands use
+///     - words after the type of `a`, `e` and `d` answer its prompts, `key=value` by name and
+///       the rest in order, e.g. `a tra 10/18/2026 -12.50` or `e tra 3fa2c1 amount=-13`
+pub fn to_command(command: &str, words: Vec<String>) -> Command {
+    quoted_command(
+        command,
+        words.into_iter().map(|word| (word, false)).collect(),
+    )
+}
+
+/// the Command named by command, with words that were typed in quotes marked
+///     - a quoted word is never a `key=value` answer, e.g. `"split=half"`
+fn quoted_command(command: &str, mut words: Vec<(String, bool)>) -> Command {
+    if matches!(
+        command,
+        "a" | "add" | "e" | "edit" | "d" | "delete" | "m" | "merge"
+    ) && words.len() > 1
+    {
+        add_answers(words.split_off(1));
+    }
+    let mut words: Vec<String> = words.into_iter().map(|(word, _)| word).collect();
+    // filters are parsed again later, so keep the quotes of values with spaces in them
+    if matches!(
+        command,
+        "/" | "search" | "l" | "list" | "x" | "export" | "b" | "bulk"
+    ) {
+        for word in words.iter_mut() {
+            if word.contains(char::is_whitespace) {
+                let quote = if word.contains('"') { '\'' } else { '"' };
+                *word = format!("{}{}{}", quote, word, quote);
+            }
+        }
+    }
+    let types = words.join(" ");
     match command {
         "q" => Command::Quit,
-        "l" => Command::List(types),
-        "a" => Command::Add(types),
-        "e" => Command::Edit(types),
-        "d" => Command::Delete(types),
-        "/" => Command::Search(types),
-        "?" => Command::Help,
-        "--update" => Command::Update,
+        "l" | "list" => Command::List(types),
+        "a" | "add" => Command::Add(types),
+        "e" | "edit" => Command::Edit(types),
+        "d" | "delete" => Command::Delete(types),
+        "m" | "merge" => Command::Merge(types),
+        "b" | "bulk" => Command::Bulk(types),
+        "/" | "search" => Command::Search(types),
+        "s" | "sort" => Command::Sort(types),
+        "cols" | "columns" => Command::Columns(types),
+        "i" | "import" => Command::Import(types),
+        "x" | "export" => Command::Export(types),
+        "dups" => Command::Duplicates(types),
+        "?" | "help" => Command::Help,
+        "--update" | "update" => Command::Update,
+        "--categorize" | "categorize" => Command::Categorize,
+        "rules" => Command::Rules,
         "--cancel" => Command::Cancel,
         "--roll" => Command::RollOver(types),
+        "--open" => Command::Open(types),
+        "--close" | "close" => Command::Close,
+        "--reopen" | "reopen" => Command::Reopen,
+        "tui" | "--tui" => Command::Tui,
+        "u" | "undo" => Command::Undo(types),
+        "r" | "redo" => Command::Redo(types),
         _ => Command::Empty,
     }
 }
 
+/// a command line split into its global flags, subcommand, arguments and field flags
+pub struct Invocation {
+    pub globals: HashMap<String, String>,
+    pub command: String,
+    pub args: Vec<String>,
+    pub fields: Vec<(String, String)>,
+}
+
+/// flags that take no value
+const SWITCHES: [&str; 2] = ["continue", "create"];
+
+/// split command-line arguments
+///     - `--flag value` and `--flag=value` before the subcommand are global, after it they
+///       answer the prompt with that name, e.g. `--amount -12.50` answers "Amount"
+pub fn parse_args(args: &[String]) -> Result<Invocation, String> {
+    let mut invocation = Invocation {
+        globals: HashMap::new(),
+        command: String::new(),
+        args: Vec::new(),
+        fields: Vec::new(),
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(flag) = arg.strip_prefix("--") {
+            let (key, value) = match flag.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None if SWITCHES.contains(&flag) => (flag.to_string(), String::from("yes")),
+                None => match args.next() {
+                    Some(value) => (flag.to_string(), value.clone()),
+                    None => return Err(format!("Missing a value for --{}", flag)),
+                },
+            };
+            if invocation.command.is_empty() {
+                invocation.globals.insert(key, value);
+            } else {
+                invocation.fields.push((key, value));
+            }
+        } else if invocation.command.is_empty() {
+            invocation.command = arg.clone();
+        } else {
+            invocation.args.push(arg.clone());
+        }
+    }
+    if invocation.command.is_empty() {
+        return Err(String::from("Missing a command"));
+    }
+    Ok(invocation)
+}
+
+/// answer prompts from words instead of stdin until `clear_answers`
+///     - `key=value` answers the prompt with that key, other words wait for `name_positional`
+///     - quoted words are never `key=value`
+fn add_answers(words: Vec<(String, bool)>) {
+    let mut fields = Vec::new();
+    ANSWERS.with(|answers| {
+        let mut answers = answers.borrow_mut();
+        answers.inline = true;
+        for (word, quoted) in words {
+            match word.split_once('=') {
+                Some((key, value)) if !quoted && is_key(key) => {
+                    fields.push((key.to_string(), value.to_string()))
+                }
+                _ => answers.positional.push_back(word),
+            }
+        }
+    });
+    add_named(fields);
+}
+
+/// answer the prompts with these keys instead of asking
+pub fn add_named(fields: Vec<(String, String)>) {
+    ANSWERS.with(|answers| answers.borrow_mut().named.extend(fields));
+}
+
+/// with strict set a prompt without an answer fails instead of waiting for input
+pub fn set_strict(strict: bool) {
+    ANSWERS.with(|answers| answers.borrow_mut().strict = strict);
+}
+
+/// let strict commands create the unknown names `check_name` is given
+pub fn set_create(create: bool) {
+    ANSWERS.with(|answers| answers.borrow_mut().create = create);
+}
+
+/// give the positional answers keys, in order
+pub fn name_positional(keys: &[&str]) {
+    ANSWERS.with(|answers| {
+        let mut answers = answers.borrow_mut();
+        for key in keys {
+            match answers.positional.pop_front() {
+                Some(answer) => answers.named.push((key.to_string(), answer)),
+                None => break,
+            }
+        }
+    });
+}
+
+/// the `field=value` answers of an edit, other than its id
+///     - empty when the field is asked for instead, e.g. as `--field`
+pub fn edit_fields() -> Vec<(String, String)> {
+    ANSWERS.with(|answers| {
+        let mut answers = answers.borrow_mut();
+        if answers
+            .named
+            .iter()
+            .any(|(key, _)| key == "field" || key == "field-to-edit")
+        {
+            return Vec::new();
+        }
+        let (ids, fields) = answers.named.drain(..).partition(|(key, _)| key == "id");
+        answers.named = ids;
+        fields
+            .into_iter()
+            .map(|(key, value)| (key.replace('-', " "), value))
+            .collect()
+    })
+}
+
+/// run edit once per field of `edit_fields`, or once for the "Field to edit" asked for
+///     - returns what each edit returned
+pub fn for_each_field<T>(fields: &[(String, String)], mut edit: impl FnMut(&str) -> T) -> Vec<T> {
+    if fields.is_empty() {
+        return vec![edit(&get_input("Field to edit"))];
+    }
+    let mut results = Vec::new();
+    for (field, value) in fields {
+        ANSWERS.with(|answers| answers.borrow_mut().next = Some(value.clone()));
+        results.push(edit(field));
+    }
+    results
+}
+
+/// forget any answers left over from a command, warning about them
+pub fn clear_answers() {
+    ANSWERS.with(|answers| {
+        let answers = answers.replace(Answers::default());
+        let unused: Vec<String> = answers
+            .named
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .chain(answers.positional)
+            .chain(answers.next)
+            .collect();
+        if !unused.is_empty() {
+            let message = format!("Ignored: {}", unused.join(" "));
+            if !capture(&message) {
+                eprintln!("{}", message);
+            }
+        }
+    });
+}
+
+/// read commands and answers from lines instead of the terminal, without echoing prompts
+///     - tables are printed as tab-separated values and messages go to stderr
+pub fn start_batch(lines: Box<dyn BufRead>) {
+    SCRIPT.with(|script| *script.borrow_mut() = Some(Script { lines, line: 0 }));
+}
+
+/// whether commands come from a script
+pub fn is_batch() -> bool {
+    SCRIPT.with(|script| script.borrow().is_some())
+}
+
+/// the number of the last line read from the script
+pub fn batch_line() -> usize {
+    SCRIPT.with(|script| script.borrow().as_ref().map_or(0, |script| script.line))
+}
+
+pub fn note(message: &str) {
+    if capture(message) {
+        return;
+    }
+    if is_batch() {
+        eprintln!("{}", message);
+    } else {
+        println!("{}", message);
+    }
+}
+
+/// report an error and remember that the current command failed
+pub fn fail(message: &str) {
+    if !capture(message) {
+        eprintln!("{}", message);
+    }
+    FAILED.with(|failed| failed.set(true));
+}
+
+/// collect messages instead of printing them, e.g. while the full-screen view is up
+pub fn capture_notes(capturing: bool) {
+    NOTES.with(|notes| *notes.borrow_mut() = if capturing { Some(Vec::new()) } else { None });
+}
+
+/// the messages collected since the last call
+pub fn take_notes() -> Vec<String> {
+    NOTES.with(|notes| {
+        notes
+            .borrow_mut()
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    })
+}
+
+/// keep message if messages are being collected, returns whether it was kept
+fn capture(message: &str) -> bool {
+    NOTES.with(|notes| match notes.borrow_mut().as_mut() {
+        Some(notes) => {
+            notes.push(message.to_string());
+            true
+        }
+        None => false,
+    })
+}
+
+/// whether anything failed since the last call
+pub fn take_failure() -> bool {
+    FAILED.with(|failed| failed.replace(false))
+}
+
+/// the flag name answering a prompt: "Only on Account (blank for any)" is `only-on-account`
+fn field_key(arg: &str) -> String {
+    arg.split(['(', '[', '?'])
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_lowercase()
+        .replace(' ', "-")
+}
+
+fn is_key(word: &str) -> bool {
+    !word.is_empty()
+        && word
+            .chars()
+            .all(|c| c.is_ascii_alphabetic() || c == '-' || c == '_')
+}
+
+/// the preset answer to a prompt, if answers were given
+///     - the full key is tried first, then its first word (`--field` for "Field to edit"), then
+///       a key of at least three letters it starts with (`min` for "Minimum amount")
+///     - after inline values a prompt that may be left blank (or has a default) is not asked
+///     - a strict prompt without an answer is blank, and fails unless it may be left blank
+fn preset(arg: &str, optional: bool) -> Option<String> {
+    ANSWERS.with(|answers| {
+        let mut answers = answers.borrow_mut();
+        if let Some(answer) = answers.next.take() {
+            return Some(answer);
+        }
+        let key = field_key(arg);
+        let short = key.split('-').next().unwrap_or("").to_string();
+        let position = answers
+            .named
+            .iter()
+            .position(|(name, _)| name.replace('_', "-") == key)
+            .or_else(|| answers.named.iter().position(|(name, _)| *name == short))
+            .or_else(|| {
+                answers
+                    .named
+                    .iter()
+                    .position(|(name, _)| name.len() >= 3 && key.starts_with(name.as_str()))
+            });
+        if let Some(position) = position {
+            return Some(answers.named.remove(position).1);
+        }
+        if answers.inline && !answers.strict && (optional || arg.contains("blank")) {
+            return Some(String::new());
+        }
+        if answers.strict {
+            if !optional && !arg.contains("blank") {
+                fail(&format!("No answer for {}, pass it as --{}", arg, key));
+            }
+            return Some(String::new());
+        }
+        None
+    })
+}
+
 pub fn get_input(arg: &str) -> String {
+    if let Some(answer) = preset(arg, false) {
+        return answer;
+    }
+    read_line(arg).unwrap_or_else(|| {
+        fail(&format!("Input ended before {} was answered", arg));
+        String::new()
+    })
+}
+
+/// ask arg on the terminal, None at the end of input
+fn read_line(arg: &str) -> Option<String> {
+    if let Some(line) = SCRIPT.with(|script| {
+        script.borrow_mut().as_mut().map(|script| {
+            let mut buffer = String::new();
+            match script.lines.read_line(&mut buffer) {
+                Ok(0) => None,
+                Ok(_) => {
+                    script.line += 1;
+                    Some(buffer.trim().to_string())
+                }
+                Err(e) => {
+                    fail(&format!("Error reading script: {}", e));
+                    None
+                }
+            }
+        })
+    }) {
+        return line;
+    }
+    if let Some(line) = editor::read_line(arg) {
+        return line.map(|line| line.trim().to_string());
+    }
     print!("{}: ", arg);
     io::stdout().flush().unwrap();
     let mut buffer = String::new();
     match io::stdin().read_line(&mut buffer) {
+        Ok(0) => return None,
         Ok(u) => u,
         Err(e) => {
             eprintln!("Error getting input: {}", e);
-            return String::new();
+            return Some(String::new());
         }
     };
-    String::from(buffer.trim())
+    Some(String::from(buffer.trim()))
 }
 
-pub fn try_into_money(possible_num: &mut String) -> f32 {
-    match possible_num.parse() {
-        Ok(num) => num,
-        Err(_) => {
-            possible_num.push_str(".0");
-            println!("{}", possible_num);
-            match possible_num.parse() {
-                Ok(num) => num,
-                Err(e) => {
-                    eprintln!("Error converting Expected value: {}", e);
-                    eprintln!("Substituting 0.0, edit if not satisfactory");
-                    0.0
+/// like `get_input`, but shows a default that a blank answer keeps
+pub fn get_input_or(arg: &str, default: &str) -> String {
+    if default.is_empty() {
+        return get_input(arg);
+    }
+    if let Some(answer) = preset(arg, true) {
+        return if answer.is_empty() {
+            default.to_string()
+        } else {
+            answer
+        };
+    }
+    let input = get_input(&format!("{} [{}]", arg, default));
+    if input.is_empty() {
+        default.to_string()
+    } else {
+        input
+    }
+}
+
+/// check an entered name against the known ones, e.g. Account names for a Transaction
+///     - an unknown name can be swapped for the closest known one, created or kept as is
+///     - Enter takes the closest name if there is one, otherwise keeps the name
+///     - the question is asked even after inline answers
+///     - strict commands can't ask, so an unknown name fails (naming the closest one) unless
+///       `--create` was given
+///     - returns the name to use and whether it should be created, None if the name failed
+pub fn check_name(kind: &str, name: String, known: &[&str]) -> Option<(String, bool)> {
+    if name.is_empty() || name == "<empty>" || known.contains(&name.as_str()) {
+        return Some((name, false));
+    }
+    let suggestion = closest(&name, known);
+    let (question, default) = match suggestion {
+        Some(close) => (
+            format!(
+                "No {} {}, did you mean {}? (y)es, (c)reate it, (k)eep it",
+                kind, name, close
+            ),
+            "y",
+        ),
+        None => (format!("No {} {}: (c)reate it, (k)eep it", kind, name), "k"),
+    };
+    let (strict, create) = ANSWERS.with(|answers| {
+        let answers = answers.borrow();
+        (answers.strict, answers.create)
+    });
+    let answer = if strict && !create {
+        match suggestion {
+            Some(close) => fail(&format!("No {} {}, did you mean {}?", kind, name, close)),
+            None => fail(&format!("No {} {}", kind, name)),
+        }
+        return None;
+    } else if strict {
+        String::from("c")
+    } else {
+        get_input(&format!("{} [{}]", question, default))
+    };
+    let answer = if answer.is_empty() {
+        default
+    } else {
+        answer.as_str()
+    };
+    match (answer, suggestion) {
+        ("y" | "yes", Some(close)) => Some((close.to_string(), false)),
+        ("k" | "keep", _) => Some((name, false)),
+        _ => {
+            note!("New {} {}", kind, name);
+            Some((name, true))
+        }
+    }
+}
+
+/// the known name closest to name, ignoring case
+///     - None unless at most a third of the letters (and at least one) need changing
+pub fn closest<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let allowed = (name.len() / 3).max(1);
+    known
+        .iter()
+        .map(|candidate| {
+            let other: Vec<char> = candidate.to_lowercase().chars().collect();
+            (edit_distance(&name, &other), *candidate)
+        })
+        .filter(|(distance, _)| *distance <= allowed)
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, candidate)| candidate)
+}
+
+/// how many characters must be inserted, removed or replaced to turn a into b
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let replace = previous[j] + usize::from(ca != cb);
+            current.push(replace.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// the id saved in a .cls file, or a new one for files written before ids were saved
+pub fn id_from_cls(possible_id: Option<&str>) -> Uuid {
+    possible_id
+        .and_then(|id| Uuid::parse_str(id).ok())
+        .unwrap_or_else(Uuid::new_v4)
+}
+
+/// read an amount, working out sums like `12.49+3.20*2-1` or `-(45/3)`
+///     - the result of a sum is shown before it is used, rounded to cents
+pub fn try_into_money(possible_num: &str) -> f32 {
+    if let Ok(num) = possible_num.trim().parse() {
+        return num;
+    }
+    match evaluate(possible_num) {
+        Ok(num) => {
+            let num = money_round(num as f32);
+            note!("{} = {}", possible_num.trim(), num);
+            num
+        }
+        Err(e) => {
+            fail(&format!(
+                "Error converting amount {:?}: {}",
+                possible_num, e
+            ));
+            eprintln!("Substituting 0.0, edit if not satisfactory");
+            0.0
+        }
+    }
+}
+
+/// work out an arithmetic expression of numbers, `+ - * /` (or `x`) and parentheses
+///     - `$` and spaces are ignored, and `,` only between groups of three digits, so
+///       `$1,200 / 3` works but `12,50` does not
+fn evaluate(expression: &str) -> Result<f64, String> {
+    let chars: Vec<char> = expression
+        .chars()
+        .filter(|c| *c != '$' && !c.is_whitespace())
+        .collect();
+    if chars.is_empty() {
+        return Err(String::from("nothing entered"));
+    }
+    let mut at = 0;
+    let value = sum(&chars, &mut at)?;
+    match chars.get(at) {
+        None => Ok(value),
+        Some(c) => Err(format!("unexpected {:?}", c)),
+    }
+}
+
+fn sum(chars: &[char], at: &mut usize) -> Result<f64, String> {
+    let mut value = product(chars, at)?;
+    while let Some(&op) = chars.get(*at).filter(|c| matches!(c, '+' | '-')) {
+        *at += 1;
+        let right = product(chars, at)?;
+        value = if op == '+' {
+            value + right
+        } else {
+            value - right
+        };
+    }
+    Ok(value)
+}
+
+fn product(chars: &[char], at: &mut usize) -> Result<f64, String> {
+    let mut value = factor(chars, at)?;
+    while let Some(&op) = chars.get(*at).filter(|c| matches!(c, '*' | 'x' | '/')) {
+        *at += 1;
+        let right = factor(chars, at)?;
+        if op == '/' {
+            if right == 0.0 {
+                return Err(String::from("division by zero"));
+            }
+            value /= right;
+        } else {
+            value *= right;
+        }
+    }
+    Ok(value)
+}
+
+fn factor(chars: &[char], at: &mut usize) -> Result<f64, String> {
+    match chars.get(*at) {
+        Some('-') => {
+            *at += 1;
+            Ok(-factor(chars, at)?)
+        }
+        Some('+') => {
+            *at += 1;
+            factor(chars, at)
+        }
+        Some('(') => {
+            *at += 1;
+            let value = sum(chars, at)?;
+            if chars.get(*at) != Some(&')') {
+                return Err(String::from("missing )"));
+            }
+            *at += 1;
+            Ok(value)
+        }
+        Some(_) => {
+            let start = *at;
+            let mut number = String::new();
+            while let Some(&c) = chars.get(*at) {
+                if c.is_ascii_digit() || c == '.' {
+                    number.push(c);
+                } else if c != ','
+                    || number.is_empty()
+                    || number.contains('.')
+                    || !group(chars, *at + 1)
+                {
+                    break;
                 }
+                *at += 1;
             }
+            number
+                .parse()
+                .map_err(|_| format!("expected a number at {:?}", chars[start]))
         }
+        None => Err(String::from("ended early")),
     }
 }
 
+/// whether exactly three digits start at chars[at], the group after a thousands `,`
+fn group(chars: &[char], at: usize) -> bool {
+    chars.len() >= at + 3
+        && chars[at..at + 3].iter().all(char::is_ascii_digit)
+        && !chars.get(at + 3).is_some_and(char::is_ascii_digit)
+}
+
 pub fn money_round(unrounded: f32) -> f32 {
     (unrounded * 100.0).round() / 100.0
 }
 
-pub fn try_into_date(possible_date: &String) -> DateTime<Utc> {
-    let dt = NaiveDate::parse_from_str(possible_date, "%m/%d/%Y").expect("Couldn't parse date");
-    DateTime::<Utc>::from_utc(dt.and_hms(0, 0, 0), Utc)
+/// the environment variable holding an extra date format to accept, e.g. `%d.%m.%Y`
+pub const DATE_FORMAT_VAR: &str = "BUDGETERS_DATE_FORMAT";
+
+/// remember the (year, month) being worked on, for dates given as a day of the month
+pub fn set_open_month(month: Option<(i32, u32)>) {
+    OPEN_MONTH.with(|open| open.set(month));
+}
+
+/// the date a blank date prompt means
+///     - today, or the same day of the open month when another month is open
+pub fn default_date() -> NaiveDate {
+    let today = Local::now().date_naive();
+    match OPEN_MONTH.with(Flag::get) {
+        Some((year, month)) if (year, month) != (today.year(), today.month()) => {
+            // the 31st of a shorter month becomes its last day
+            (1..=today.day())
+                .rev()
+                .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+                .unwrap_or(today)
+        }
+        _ => today,
+    }
+}
+
+/// read a date the way the date prompts accept it
+///     - `today`, `yesterday`, `tomorrow`, or days/weeks from today like `-3d` and `+1w`
+///     - a day alone (`15`) is that day of the open month
+///     - MM/DD/YYYY, ISO YYYY-MM-DD, or the format in `BUDGETERS_DATE_FORMAT` (chrono syntax)
+///     - blank is `default_date`
+pub fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim().to_lowercase();
+    let today = Local::now().date_naive();
+    match text.as_str() {
+        "" => return Some(default_date()),
+        "today" => return Some(today),
+        "yesterday" => return today.pred_opt(),
+        "tomorrow" => return today.succ_opt(),
+        _ => (),
+    }
+    if let Some(count) = text.strip_suffix(['d', 'w']) {
+        if count.starts_with(['-', '+']) {
+            let days = count.parse::<i64>().ok()? * if text.ends_with('w') { 7 } else { 1 };
+            return today.checked_add_signed(Duration::days(days));
+        }
+    }
+    if let Ok(day) = text.parse::<u32>() {
+        let (year, month) = OPEN_MONTH
+            .with(Flag::get)
+            .unwrap_or((today.year(), today.month()));
+        return NaiveDate::from_ymd_opt(year, month, day);
+    }
+    let mut formats = vec![String::from("%m/%d/%Y"), String::from("%Y-%m-%d")];
+    if let Ok(format) = env::var(DATE_FORMAT_VAR) {
+        // the configured format wins where it reads a date differently, e.g. %d/%m/%Y
+        formats.insert(0, format);
+    }
+    formats
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(&text, format).ok())
+}
+
+pub fn try_into_date(possible_date: &str) -> DateTime<Utc> {
+    match parse_date(possible_date) {
+        Some(date) => Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)),
+        None => {
+            fail(&format!(
+                "Error converting date {:?}: expected MM/DD/YYYY, YYYY-MM-DD, a day, today or -3d",
+                possible_date
+            ));
+            eprintln!("Substituting today, edit if not satisfactory");
+            Utc.from_utc_datetime(&Utc::now().date_naive().and_time(NaiveTime::MIN))
+        }
+    }
+}
+
+/// ask for a date, offering `default_date` for a blank answer
+pub fn get_date(arg: &str) -> DateTime<Utc> {
+    let default = default_date().format("%m/%d/%Y").to_string();
+    try_into_date(&get_input_or(arg, &default))
 }
 
 pub fn try_date_to_string(date_time: DateTime<Utc>) -> String {
     date_time.to_rfc3339_opts(SecondsFormat::Millis, true)
 }
 
+/// split a line of a .cls file into its cells
+///     - cells wrapped in double quotes may contain commas, `""` is a literal quote
+pub fn split_cls(line: &str) -> Vec<String> {
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => cells.push(std::mem::take(&mut cell)),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell);
+    cells
+}
+
+/// quote a cell for a .cls file if it contains a comma or a quote
+pub fn escape_cls(cell: &str) -> String {
+    if cell.contains(',') || cell.contains('"') {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+#[derive(Clone)]
 pub enum Content {
     Num(String),
     St(String),
+    /// text with byte ranges to highlight, e.g. what a search matched
+    Marked(String, Vec<(usize, usize)>),
+    /// a value colored by what it means for the budget
+    Toned(String, Tone),
+}
+
+impl Content {
+    pub fn text(&self) -> &str {
+        match self {
+            Content::Num(text)
+            | Content::St(text)
+            | Content::Marked(text, _)
+            | Content::Toned(text, _) => text,
+        }
+    }
+
+    /// how the cell is colored, None for plain text
+    ///     - numbers are colored by sign and `<empty>` names stand out
+    pub fn tone(&self) -> Option<Tone> {
+        match self {
+            Content::Num(n) if n.contains('-') => Some(Tone::Negative),
+            Content::Num(_) => Some(Tone::Positive),
+            Content::St(s) if s == "<empty>" => Some(Tone::Empty),
+            Content::Toned(_, tone) => Some(*tone),
+            Content::St(_) | Content::Marked(..) => None,
+        }
+    }
+}
+
+/// text with the ranges wrapped in terminal highlighting (bold yellow, or bold without colors)
+///     - ranges must fall on character boundaries, overlapping ones are merged
+fn highlight(text: &str, ranges: &[(usize, usize)]) -> String {
+    let mut ranges = ranges.to_vec();
+    ranges.sort();
+    let mut marked = String::new();
+    let mut at = 0;
+    for (start, end) in ranges {
+        let start = start.max(at);
+        if end <= start {
+            continue;
+        }
+        marked.push_str(&text[at..start]);
+        // bold alone when colors are off
+        marked.push_str(if settings::colors_enabled() {
+            "\u{1b}[1;33m"
+        } else {
+            "\u{1b}[1m"
+        });
+        marked.push_str(&text[start..end]);
+        marked.push_str("\u{1b}[0m");
+        at = end;
+    }
+    marked.push_str(&text[at..]);
+    marked
+}
+
+/// the narrowest a column is squeezed to when fitting a table to the terminal
+const MIN_WIDTH: usize = 5;
+
+/// print the table for the <type> kind, with the columns chosen for it with `cols`
+pub fn make_type_table(kind: &str, headers: Vec<&str>, contents: &[Vec<Content>]) {
+    let shown = settings::columns(kind, &headers);
+    let headers = shown.iter().map(|&column| headers[column]).collect();
+    let contents: Vec<Vec<Content>> = contents
+        .iter()
+        .map(|row| {
+            shown
+                .iter()
+                .filter_map(|&column| row.get(column).cloned())
+                .collect()
+        })
+        .collect();
+    make_table(headers, &contents);
+}
+
+/// how `l` and `/` print their results
+#[derive(Clone, Copy, PartialEq)]
+pub enum Output {
+    /// a bordered table with the totals as a sentence
+    Table,
+    Json,
+    Csv,
+    Tsv,
+    /// columns lined up with spaces, without borders or colors
+    Plain,
+}
+
+impl Output {
+    pub const NAMES: [&'static str; 5] = ["table", "json", "csv", "tsv", "plain"];
+
+    /// take `-o <mode>` (or `--output <mode>`, `--output=<mode>`) out of arg
+    ///     - returns the output mode, table by default, and the rest of arg as it was written
+    pub fn take(arg: &str) -> Result<(Output, String), String> {
+        // the words of arg, with where each starts
+        let mut words = Vec::new();
+        let mut start = None;
+        for (at, c) in arg.char_indices().chain([(arg.len(), ' ')]) {
+            match (start, c.is_whitespace()) {
+                (None, false) => start = Some(at),
+                (Some(from), true) => {
+                    words.push((from, &arg[from..at]));
+                    start = None;
+                }
+                _ => (),
+            }
+        }
+        let mut output = Output::Table;
+        let mut rest = String::new();
+        let mut kept = 0;
+        let mut index = 0;
+        while index < words.len() {
+            let (from, word) = words[index];
+            let (name, end) = match word {
+                "-o" | "--output" => match words.get(index + 1) {
+                    Some((at, name)) => {
+                        index += 1;
+                        (*name, at + name.len())
+                    }
+                    None => return Err(format!("Missing an output mode after {}", word)),
+                },
+                _ => match word.strip_prefix("--output=") {
+                    Some(name) => (name, from + word.len()),
+                    None => {
+                        index += 1;
+                        continue;
+                    }
+                },
+            };
+            output = match name {
+                "table" => Output::Table,
+                "json" => Output::Json,
+                "csv" => Output::Csv,
+                "tsv" => Output::Tsv,
+                "plain" => Output::Plain,
+                _ => {
+                    return Err(format!(
+                        "Unknown output mode {}, expected one of: {}",
+                        name,
+                        Output::NAMES.join(", ")
+                    ))
+                }
+            };
+            rest.push_str(&arg[kept..from]);
+            kept = end;
+            index += 1;
+        }
+        rest.push_str(&arg[kept..]);
+        Ok((output, rest.trim().to_string()))
+    }
+}
+
+/// a listed table and the totals worked out for it
+pub struct Report<'a> {
+    /// the <type> whose columns `cols` chooses
+    pub kind: &'a str,
+    pub title: &'a str,
+    pub headers: Vec<&'a str>,
+    pub contents: Vec<Vec<Content>>,
+    /// named totals, e.g. ("worth", 120.5)
+    pub totals: Vec<(&'a str, f32)>,
+    /// the totals as a sentence for people, empty for none
+    pub summary: String,
+    /// print the summary under the table rather than above it
+    pub summary_below: bool,
+}
+
+impl Report<'_> {
+    pub fn print(&self, output: Output) {
+        if output == Output::Table {
+            note!("{}", self.title);
+            if !self.summary.is_empty() && !self.summary_below {
+                note!("{}", self.summary);
+            }
+            make_type_table(self.kind, self.headers.clone(), &self.contents);
+            if !self.summary.is_empty() && self.summary_below {
+                note!("{}", self.summary);
+            }
+            return;
+        }
+        let shown = settings::columns(self.kind, &self.headers);
+        let headers: Vec<&str> = shown.iter().map(|&column| self.headers[column]).collect();
+        let rows: Vec<Vec<&Content>> = self
+            .contents
+            .iter()
+            .map(|row| shown.iter().filter_map(|&column| row.get(column)).collect())
+            .collect();
+        let totals: Vec<(&str, String)> = self
+            .totals
+            .iter()
+            .map(|(name, total)| (*name, money_round(*total).to_string()))
+            .collect();
+        match output {
+            Output::Json => println!("{}", self.to_json(&headers, &rows, &totals)),
+            Output::Csv | Output::Tsv => {
+                let escape = |cell: &str| match output {
+                    Output::Csv => escape_cls(&cell.replace('\n', " ")),
+                    _ => cell.replace(['\t', '\n'], " "),
+                };
+                let separator = if output == Output::Csv { "," } else { "\t" };
+                let line = |cells: Vec<&str>| {
+                    cells
+                        .into_iter()
+                        .map(escape)
+                        .collect::<Vec<String>>()
+                        .join(separator)
+                };
+                println!("{}", line(headers.clone()));
+                for row in &rows {
+                    println!("{}", line(row.iter().map(|c| c.text()).collect()));
+                }
+                if !totals.is_empty() {
+                    println!();
+                    for (name, total) in &totals {
+                        println!("{}", line(vec![name, total]));
+                    }
+                }
+            }
+            _ => {
+                let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
+                for row in &rows {
+                    for (column, c) in row.iter().enumerate() {
+                        widths[column] = widths[column].max(c.text().chars().count());
+                    }
+                }
+                let line = |cells: Vec<&str>| {
+                    cells
+                        .iter()
+                        .zip(&widths)
+                        .map(|(cell, width)| format!("{:<1$}", cell, width))
+                        .collect::<Vec<String>>()
+                        .join("  ")
+                        .trim_end()
+                        .to_string()
+                };
+                println!("{}", line(headers.clone()));
+                for row in &rows {
+                    println!("{}", line(row.iter().map(|c| c.text()).collect()));
+                }
+                if !totals.is_empty() {
+                    println!();
+                    let width = totals.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+                    for (name, total) in &totals {
+                        println!("{:<2$}  {}", name, total, width);
+                    }
+                }
+            }
+        }
+    }
+
+    /// `{"type": ..., "columns": [...], "rows": [{column: value}], "totals": {name: total}}`
+    ///     - number cells are JSON numbers, everything else strings
+    fn to_json(
+        &self,
+        headers: &[&str],
+        rows: &[Vec<&Content>],
+        totals: &[(&str, String)],
+    ) -> String {
+        let columns: Vec<String> = headers.iter().map(|h| json_string(h)).collect();
+        let rows: Vec<String> = rows
+            .iter()
+            .map(|row| {
+                let fields: Vec<String> = headers
+                    .iter()
+                    .zip(row)
+                    .map(|(header, c)| {
+                        let value = match c {
+                            Content::Num(n) | Content::Toned(n, _) if n.parse::<f64>().is_ok() => {
+                                n.clone()
+                            }
+                            _ => json_string(c.text()),
+                        };
+                        format!("{}: {}", json_string(header), value)
+                    })
+                    .collect();
+                format!("    {{{}}}", fields.join(", "))
+            })
+            .collect();
+        let totals: Vec<String> = totals
+            .iter()
+            .map(|(name, total)| format!("{}: {}", json_string(name), total))
+            .collect();
+        format!(
+            "{{\n  \"type\": {},\n  \"columns\": [{}],\n  \"rows\": [\n{}\n  ],\n  \"totals\": {{{}}}\n}}",
+            json_string(self.kind),
+            columns.join(", "),
+            rows.join(",\n"),
+            totals.join(", ")
+        )
+    }
 }
 
+/// text as a JSON string, quoted and escaped
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// print a table, as tab-separated values in batch mode
+///     - on a terminal, long cells are cut or wrapped to fit its width and the rows are shown a
+///       screenful at a time
 pub fn make_table(headers: Vec<&str>, contents: &Vec<Vec<Content>>) {
-    let mut table = Table::new();
-    // add headers to the table
-    table.add_row(Row::new({
+    if is_batch() {
+        println!("{}", headers.join("\t"));
+        for row in contents {
+            let cells: Vec<&str> = row.iter().map(Content::text).collect();
+            println!("{}", cells.join("\t"));
+        }
+        return;
+    }
+    let size = terminal_size();
+    let fit = settings::cell_fit();
+    // the same widths on every page, narrowed to the terminal unless cells are shown in full
+    let widths = size.map(|(_, width)| {
+        let width = if fit == CellFit::Full {
+            usize::MAX
+        } else {
+            width
+        };
+        column_widths(&headers, contents, width)
+    });
+    // style: center & bold text, Yellow color when colors are on
+    let header_style = if settings::colors_enabled() {
+        "cbFy"
+    } else {
+        "cb"
+    };
+    let header = Row::new(
+        headers
+            .iter()
+            .enumerate()
+            .map(|(column, h)| {
+                let text = match &widths {
+                    Some(widths) => fit_cell(h, &[], widths[column], false).remove(0).0,
+                    None => h.to_string(),
+                };
+                Cell::new(&text).style_spec(header_style)
+            })
+            .collect(),
+    );
+    let mut rows = Vec::new();
+    let mut heights = Vec::new();
+    for row in contents {
+        let mut height = 1;
         let mut cells = Vec::new();
-        for h in headers {
-            cells.push(
-                // style: center & bold text, Yellow color
-                Cell::new(h).style_spec("cbFy"),
+        for (column, c) in row.iter().enumerate() {
+            let (text, ranges) = match c {
+                Content::Marked(s, ranges) if io::stdout().is_terminal() => {
+                    (s.as_str(), &ranges[..])
+                }
+                _ => (c.text(), &[][..]),
+            };
+            let lines = match &widths {
+                Some(widths) => fit_cell(text, ranges, widths[column], fit == CellFit::Wrap)
+                    .into_iter()
+                    .map(|(line, marked)| (format!("{:<1$}", line, widths[column]), marked))
+                    .collect(),
+                None => vec![(text.to_string(), ranges.to_vec())],
+            };
+            height = height.max(lines.len());
+            let text = lines
+                .iter()
+                .map(|(line, marked)| highlight(line, marked))
+                .collect::<Vec<String>>()
+                .join("\n");
+            cells.push(match c.tone().and_then(settings::style_spec) {
+                Some(spec) => Cell::new(&text).style_spec(&spec),
+                None => Cell::new(&text),
+            });
+        }
+        rows.push(Row::new(cells));
+        heights.push(height);
+    }
+    // page only when someone is there to ask for the next one
+    let room = match size {
+        Some((height, _)) if settings::paging() && io::stdin().is_terminal() => Some(height),
+        _ => None,
+    };
+    let pages = pages(&heights, room);
+    for (number, page) in pages.iter().enumerate() {
+        let mut table = Table::new();
+        table.add_row(header.clone());
+        for row in &rows[page.clone()] {
+            table.add_row(row.clone());
+        }
+        table.printstd();
+        if number + 1 < pages.len() {
+            let prompt = format!(
+                "Page {} of {}, Enter for more or q to stop",
+                number + 1,
+                pages.len()
             );
+            match read_line(&prompt) {
+                Some(answer) if answer != "q" => (),
+                _ => break,
+            }
+        }
+    }
+}
+
+/// (rows, columns) of the terminal stdout goes to, None when it is not one
+pub fn terminal_size() -> Option<(usize, usize)> {
+    #[cfg(unix)]
+    if io::stdout().is_terminal() {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        // SAFETY: TIOCGWINSZ fills in the winsize passed in
+        let found = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) } == 0;
+        if found && size.ws_row > 0 && size.ws_col > 0 {
+            return Some((size.ws_row as usize, size.ws_col as usize));
         }
-        cells
-    }));
+    }
+    None
+}
+
+/// column widths that fit a table in width characters, narrowing the widest columns first
+fn column_widths(headers: &[&str], contents: &[Vec<Content>], width: usize) -> Vec<usize> {
+    let mut widths: Vec<usize> = headers.iter().map(|h| h.chars().count()).collect();
     for row in contents {
-        table.add_row(Row::new({
-            let mut cells = Vec::new();
-            for c in row {
-                cells.push(match c {
-                    Content::Num(n) => {
-                        if n.contains("-") {
-                            Cell::new(n).style_spec("Fr")
-                        } else {
-                            Cell::new(n).style_spec("Fg")
-                        }
-                    }
-                    Content::St(s) => {
-                        if s.contains("<empty>") {
-                            Cell::new(s).style_spec("Fb")
-                        } else {
-                            Cell::new(s)
-                        }
-                    }
-                });
+        for (column, c) in row.iter().enumerate().take(widths.len()) {
+            widths[column] = widths[column].max(c.text().chars().count());
+        }
+    }
+    // each cell is padded by a space on both sides, with a border between them
+    while widths.iter().sum::<usize>() + 3 * widths.len() + 1 > width {
+        let widest = (0..widths.len()).max_by_key(|&c| widths[c]).unwrap_or(0);
+        if widths.get(widest).is_none_or(|&w| w <= MIN_WIDTH) {
+            break;
+        }
+        widths[widest] -= 1;
+    }
+    widths
+}
+
+/// the lines a cell shows in width characters, with the ranges to highlight on each
+///     - cut text ends in `…`, wrapped text breaks after a space where it can
+fn fit_cell(
+    text: &str,
+    ranges: &[(usize, usize)],
+    width: usize,
+    wrap: bool,
+) -> Vec<(String, Vec<(usize, usize)>)> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    if chars.len() <= width || width == 0 {
+        return vec![(text.to_string(), ranges.to_vec())];
+    }
+    let byte = |index: usize| chars.get(index).map_or(text.len(), |(at, _)| *at);
+    let mut lines = Vec::new();
+    if wrap {
+        let mut start = 0;
+        while chars.len() - start > width {
+            let window = &chars[start..=start + width];
+            let cut = window
+                .iter()
+                .rposition(|(_, c)| c.is_whitespace())
+                .filter(|&at| at > 0)
+                .unwrap_or(width);
+            lines.push((byte(start), byte(start + cut)));
+            start += cut;
+            while start < chars.len() && chars[start].1.is_whitespace() {
+                start += 1;
             }
-            cells
-        }));
+        }
+        lines.push((byte(start), text.len()));
+    } else {
+        lines.push((0, byte(width - 1)));
     }
-    table.printstd();
+    lines
+        .into_iter()
+        .map(|(from, to)| {
+            let mut line = text[from..to].to_string();
+            if !wrap {
+                line.push('…');
+            }
+            let marked = ranges
+                .iter()
+                .filter_map(|&(start, end)| {
+                    let (start, end) = (start.max(from), end.min(to));
+                    (start < end).then(|| (start - from, end - from))
+                })
+                .collect();
+            (line, marked)
+        })
+        .collect()
+}
+
+/// the rows on each page, so header and rows fit in room lines
+///     - all on one page without room, and at least one row per page
+fn pages(heights: &[usize], room: Option<usize>) -> Vec<std::ops::Range<usize>> {
+    // leaving lines for notes above the table and the prompt below it
+    let room = room.map_or(usize::MAX, |height| height.saturating_sub(3));
+    let mut pages = Vec::new();
+    let mut start = 0;
+    // the top border, the header and the line under it
+    let mut used = 3;
+    for (index, height) in heights.iter().enumerate() {
+        if used + height + 1 > room && index > start {
+            pages.push(start..index);
+            start = index;
+            used = 3;
+        }
+        used += height + 1;
+    }
+    pages.push(start..heights.len());
+    pages
+}
+
+/// usage of the command-line subcommands
+pub fn print_usage() {
+    println!(
+        "usage: budgeters [--year YYYY] [--month MM] <command> [<type>] [--<field> <value>...]"
+    );
+    println!("       budgeters            start the interactive prompt");
+    println!();
+    println!("The month defaults to the current one. Commands are the long or short names of the");
+    println!("prompt commands: add (a), edit (e), delete (d), merge (m), bulk (b), list (l), search (/), import (i),");
+    println!("export (x), dups, update, categorize, rules, close, reopen, tui, help (?).");
+    println!("Fields answer the prompts a command would ask, named after the prompt:");
+    println!("  budgeters --year 2026 --month 10 add tra --date 10/18/2026 --amount -12.50 \\");
+    println!("      --account Checking --category Food --description Lunch");
+    println!("  budgeters edit tra --id 3fa2c1 --field amount --amount -13");
+    println!("  budgeters list tra --output json cat=Food");
+    println!("  budgeters --year 2026 --month 10 batch monthly-setup.txt [--continue]");
+    println!("batch runs the prompt commands in a file (or stdin for `-`), each followed by the");
+    println!("answers to its prompts, and prints tables as tab-separated values.");
+    println!("An unknown account, category or payee name fails (suggesting a close one) unless");
+    println!("--create is given to add it.");
+    println!("Changes are saved unless something fails. Exit status: 0 on success, 1 when the");
+    println!("command failed, 2 for a usage error.");
 }
 
 pub fn print_help() {
     println!("==== HELP ====");
     println!(">>> Abbreviations (replace <type> with these)");
-    println!("Account -> acc, Category -> cat, Transaction -> tra"); // TODO: add Transfer
+    println!("Account -> acc, Category -> cat, Transaction -> tra, Rule -> rul, Payee -> pay"); // TODO: add Transfer
     println!();
     println!(">>> Commands");
     println!("? : prints this out");
-    println!("a <type> : initiate add method for either <Account>, <Category>, or <Transaction>");
-    println!("e <type> : initiate edit method for <type>");
-    println!("d <type> : initiate delete script for <type>");
-    println!("l <type> : list the table for the <type>");
-    println!("/ <query> : search <Transaction> table by the <query>");
+    println!(
+        "a <type> [values] : initiate add method for <type>, values answer its prompts in order"
+    );
+    println!("e <type> [id] [field=value...] : initiate edit method for <type>");
+    println!("d <type> [id] : initiate delete script for <type>");
+    println!("b <filter> [set field=value...] : change the category, account, tags or description of every matching <Transaction>, after a preview");
+    println!("m <acc|cat> [id] [into-id] : merge an <Account> or <Category> into another, moving its <Transaction>s");
+    println!("l <type> [-o mode] [filter] : list the table for the <type>, <Transaction>s can be narrowed by a filter");
+    println!("/ [-o mode] <filter> : search <Transaction> table by the <filter>, e.g. `category=Food amount<-50 desc~coffee`");
+    println!("    output modes: table (default), json, csv, tsv, plain, with the totals");
+    println!("    filter terms: date, amount, account, category, description, tags with = != < <= > >= ~ !~, combined with and/or/not/( )");
+    println!("    filter options first: -i ignore case, -r regex, -f fuzzy (best matches first)");
+    println!("s [type] [field [asc|desc]...] : sort the <type> (default tra) tables by fields for the session, later fields break ties");
+    println!("cols [type] [columns|-column|+column|all] : choose the columns the <type> table shows, kept in settings");
+    println!(
+        "i <format> <path> : import a statement file into <Transaction> (ofx, qfx, qif, ledger)"
+    );
+    println!("x <format> <path> [YYYY/MM..YYYY/MM] [where <filter>] : export the open month, or a range of months, to a file (qif, ledger, beancount)");
+    println!("dups [days] : list suspected duplicate <Transaction> pairs (default 3 days apart) and merge them");
+    println!("rules : show which <Rule> matches each <Transaction>");
+    println!("--categorize : apply the <Rule>s to every <empty> <Transaction>");
+    println!("u [n] : undo the last (or last n) changes made by a, e, d, m, b, i, dups, --roll, --update or --categorize");
+    println!("r [n] : redo the last (or last n) undone changes");
+    println!("--open [YYYY MM] : save the open month and switch to another (created when saved)");
+    println!("--close : save the open month and mark it read-only");
+    println!("--reopen : make the closed open month editable again");
+    println!("tui : browse and edit the open month full-screen, `q` goes back to the prompt");
     println!("q : quits the app and saves the files into the correct subdirectory");
     println!("--update : update the budget spread (update <Account> value and <Category> actual)");
     println!("--cancel : quits the app and does not save any updates");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> Option<NaiveDate> {
+        NaiveDate::from_ymd_opt(year, month, day)
+    }
+
+    fn close(result: Result<f64, String>, expected: f64) -> bool {
+        result.is_ok_and(|value| (value - expected).abs() < 1e-9)
+    }
+
+    #[test]
+    fn parse_date_formats() {
+        assert_eq!(parse_date("10/18/2026"), date(2026, 10, 18));
+        assert_eq!(parse_date("2026-10-18"), date(2026, 10, 18));
+        assert_eq!(parse_date(" 10/18/2026 "), date(2026, 10, 18));
+        assert_eq!(parse_date("13/40/2026"), None);
+        assert_eq!(parse_date("soon"), None);
+    }
+
+    #[test]
+    fn parse_date_relative() {
+        let today = Local::now().date_naive();
+        assert_eq!(parse_date("today"), Some(today));
+        assert_eq!(parse_date("Yesterday"), today.pred_opt());
+        assert_eq!(parse_date("tomorrow"), today.succ_opt());
+        assert_eq!(parse_date("-3d"), Some(today - Duration::days(3)));
+        assert_eq!(parse_date("+1w"), Some(today + Duration::days(7)));
+        // a count without a sign is not relative
+        assert_eq!(parse_date("3d"), None);
+        assert_eq!(parse_date("-xd"), None);
+    }
+
+    #[test]
+    fn parse_date_day_of_open_month() {
+        set_open_month(Some((2026, 11)));
+        assert_eq!(parse_date("15"), date(2026, 11, 15));
+        assert_eq!(parse_date("31"), None);
+        set_open_month(None);
+    }
+
+    #[test]
+    fn evaluate_arithmetic() {
+        assert!(close(evaluate("12.49+3.20*2-1"), 17.89));
+        assert!(close(evaluate("2+3*4"), 14.0));
+        assert!(close(evaluate("2*(3+4)"), 14.0));
+        assert!(close(evaluate("-(45/3)"), -15.0));
+        assert!(close(evaluate("2x3"), 6.0));
+        assert!(close(evaluate("10 - -2"), 12.0));
+    }
+
+    #[test]
+    fn evaluate_thousands() {
+        assert!(close(evaluate("$1,200 / 3"), 400.0));
+        assert!(close(evaluate("1,200,000.50"), 1_200_000.5));
+        assert_eq!(evaluate("12,50"), Err(String::from("unexpected ','")));
+        assert_eq!(evaluate("1,2000"), Err(String::from("unexpected ','")));
+        assert_eq!(evaluate("1.5,000"), Err(String::from("unexpected ','")));
+        assert!(evaluate(",500").is_err());
+    }
+
+    #[test]
+    fn evaluate_errors() {
+        assert_eq!(evaluate(""), Err(String::from("nothing entered")));
+        assert_eq!(evaluate("1/0"), Err(String::from("division by zero")));
+        assert_eq!(evaluate("(1+2"), Err(String::from("missing )")));
+        assert_eq!(evaluate("1+"), Err(String::from("ended early")));
+        assert_eq!(evaluate("1)"), Err(String::from("unexpected ')'")));
+        assert_eq!(
+            evaluate("abc"),
+            Err(String::from("expected a number at 'a'"))
+        );
+        assert!(evaluate("1..2").is_err());
+    }
+
+    #[test]
+    fn split_quoted_words() {
+        assert_eq!(
+            split_quoted(r#"a "b c" McDonald's 'd e' x="y z""#),
+            vec![
+                (String::from("a"), false),
+                (String::from("b c"), true),
+                (String::from("McDonald's"), false),
+                (String::from("d e"), true),
+                (String::from("x=y z"), false),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_name_strict_needs_create() {
+        let known = ["Groceries", "Rent"];
+        set_strict(true);
+        assert_eq!(
+            check_name("Category", String::from("Rent"), &known),
+            Some((String::from("Rent"), false))
+        );
+        assert!(!take_failure());
+        assert_eq!(
+            check_name("Category", String::from("Groceris"), &known),
+            None
+        );
+        assert!(take_failure());
+        set_create(true);
+        assert_eq!(
+            check_name("Category", String::from("Groceris"), &known),
+            Some((String::from("Groceris"), true))
+        );
+        assert!(!take_failure());
+        clear_answers();
+    }
+}
@@ -1,11 +1,22 @@
-use crate::cli::{self, Content};
+use crate::cli::{self, Content, Output, Report};
+use crate::settings;
+use crate::view::View;
 use account::Account;
+use bulk::Change;
 use category::Category;
+use filter::Filter;
+use payee::Payee;
+use rule::Rule;
 use std::collections::HashMap;
 use transaction::Transaction;
 
 pub mod account;
+pub mod bulk;
 pub mod category;
+pub mod duplicate;
+pub mod filter;
+pub mod payee;
+pub mod rule;
 pub mod transaction;
 
 #[derive(Clone, Debug)]
@@ -13,12 +24,16 @@ pub struct Data {
     pub accounts: Vec<Account>,
     pub categories: Vec<Category>,
     pub transactions: Vec<Transaction>,
+    pub rules: Vec<Rule>,
+    pub payees: Vec<Payee>,
 }
 
 pub enum DataType {
     Account,
     Category,
     Transaction,
+    Rule,
+    Payee,
 }
 
 impl Data {
@@ -28,6 +43,8 @@ impl Data {
             accounts: Vec::new(),
             categories: Vec::new(),
             transactions: Vec::new(),
+            rules: Vec::new(),
+            payees: Vec::new(),
         }
     }
 
@@ -39,8 +56,9 @@ impl Data {
             if line.is_empty() {
                 break;
             }
-            let mut cells = line.split(",");
-            accounts.push(Account::from_cls(cells.next(), cells.next()));
+            let cells = cli::split_cls(line);
+            let mut cells = cells.iter().map(String::as_str);
+            accounts.push(Account::from_cls(cells.next(), cells.next(), cells.next()));
         }
         self.accounts = accounts;
     }
@@ -53,8 +71,14 @@ impl Data {
             if line.is_empty() {
                 break;
             }
-            let mut cells = line.split(",");
-            categories.push(Category::from_cls(cells.next(), cells.next(), cells.next()));
+            let cells = cli::split_cls(line);
+            let mut cells = cells.iter().map(String::as_str);
+            categories.push(Category::from_cls(
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+            ));
         }
         self.categories = categories;
     }
@@ -67,41 +91,207 @@ impl Data {
             if line.is_empty() {
                 break;
             }
-            let mut cells = line.split(",");
+            let cells = cli::split_cls(line);
+            let mut cells = cells.iter().map(String::as_str);
             transactions.push(Transaction::from_cls(
                 cells.next(),
                 cells.next(),
                 cells.next(),
                 cells.next(),
                 cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
             ));
         }
         self.transactions = transactions;
     }
 
-    /// returns an array of String corresponding to the three DataTypes
-    const DATA_TYPES: [&'static str; 3] = ["acc", "cat", "tra"];
+    /// build the data.rules Vec from file contents
+    ///     - creates new Vec<Rule> if file contents are empty
+    pub fn build_rules(&mut self, contents: String) {
+        let mut rules: Vec<Rule> = Vec::new();
+        for line in contents.split("\n") {
+            if line.is_empty() {
+                break;
+            }
+            let cells = cli::split_cls(line);
+            let mut cells = cells.iter().map(String::as_str);
+            rules.push(Rule::from_cls(
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+            ));
+        }
+        self.rules = rules;
+    }
+
+    /// build the data.payees Vec from file contents
+    ///     - creates new Vec<Payee> if file contents are empty
+    pub fn build_payees(&mut self, contents: String) {
+        let mut payees: Vec<Payee> = Vec::new();
+        for line in contents.split("\n") {
+            if line.is_empty() {
+                break;
+            }
+            let cells = cli::split_cls(line);
+            let mut cells = cells.iter().map(String::as_str);
+            payees.push(Payee::from_cls(
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+                cells.next(),
+            ));
+        }
+        self.payees = payees;
+    }
+
+    /// add an Account with the given name unless one already exists
+    ///     - returns true if a new Account was added
+    pub fn ensure_account(&mut self, name: &str) -> bool {
+        if name.is_empty() || self.accounts.iter().any(|acc| acc.get_name() == name) {
+            return false;
+        }
+        self.accounts
+            .push(Account::from_cls(Some(name), None, None));
+        true
+    }
 
-    pub fn list(&self, arg: &String) {
+    /// add a Category with the given name unless one already exists
+    ///     - returns true if a new Category was added
+    pub fn ensure_category(&mut self, name: &str) -> bool {
+        if name.is_empty()
+            || name == "<empty>"
+            || self.categories.iter().any(|cat| cat.get_name() == name)
+        {
+            return false;
+        }
+        self.categories
+            .push(Category::from_cls(Some(name), None, None, None));
+        true
+    }
+
+    /// fold another month into this one
+    ///     - Accounts and Categories are matched by name, Transactions are appended
+    pub fn merge(&mut self, other: Data) {
+        for acc in other.accounts {
+            if !self.accounts.iter().any(|a| a.get_name() == acc.get_name()) {
+                self.accounts.push(acc);
+            }
+        }
+        for cat in other.categories {
+            if !self
+                .categories
+                .iter()
+                .any(|c| c.get_name() == cat.get_name())
+            {
+                self.categories.push(cat);
+            }
+        }
+        self.transactions.extend(other.transactions);
+    }
+
+    /// add a Transaction unless it looks like one already in the month and policy says to skip it
+    ///     - returns true if the Transaction was added
+    pub fn push_checked(&mut self, tra: Transaction, policy: &mut duplicate::Policy) -> bool {
+        if let Some(existing) = self
+            .transactions
+            .iter()
+            .find(|other| duplicate::is_likely(other, &tra, duplicate::DEFAULT_DAYS))
+        {
+            if !policy.allows(&tra, existing) {
+                return false;
+            }
+        }
+        self.transactions.push(tra);
+        true
+    }
+
+    /// give an uncategorized Transaction the category, account and tags of the first matching Rule
+    ///     - returns the index of the Rule that was applied
+    pub fn categorize(&self, tra: &mut Transaction) -> Option<usize> {
+        if !tra.get_category().is_empty() && tra.get_category() != "<empty>" {
+            return None;
+        }
+        let index = self.rules.iter().position(|rule| rule.matches(tra))?;
+        self.rules[index].apply(tra);
+        Some(index)
+    }
+
+    /// link a Transaction to the Payee its description names
+    ///     - an `<empty>` Category is filled from the Payee's default
+    pub fn link_payee(&self, tra: &mut Transaction) {
+        if !tra.get_payee().is_empty() {
+            return;
+        }
+        if let Some(index) = Payee::resolve(&self.payees, tra.get_description()) {
+            let payee = &self.payees[index];
+            tra.set_payee(payee.get_id());
+            if (tra.get_category().is_empty() || tra.get_category() == "<empty>")
+                && !payee.get_category().is_empty()
+            {
+                tra.set_category(payee.get_category().to_string());
+            }
+        }
+    }
+
+    /// returns an array of String corresponding to the DataTypes
+    pub const DATA_TYPES: [&'static str; 5] = ["acc", "cat", "tra", "rul", "pay"];
+
+    /// list the table for a type, `l tra` can be followed by a filter
+    pub fn list(&self, arg: &str, view: &View) {
         // expect args to have a type argument
         if arg.is_empty() {
             return;
         }
-        if arg == &Data::DATA_TYPES[0] {
-            self.display(DataType::Account);
-        } else if arg == &Data::DATA_TYPES[1] {
-            self.display(DataType::Category);
-        } else if arg == &Data::DATA_TYPES[2] {
-            self.display(DataType::Transaction);
+        let (output, arg) = match Output::take(arg) {
+            Ok(taken) => taken,
+            Err(e) => return cli::fail(&e),
+        };
+        let (arg, query) = arg.split_once(' ').unwrap_or((&arg, ""));
+        if !query.is_empty() && arg != Data::DATA_TYPES[2] {
+            cli::fail(&format!("Only tra can be filtered, not {}", arg));
+        } else if arg == Data::DATA_TYPES[2] && !query.is_empty() {
+            match Filter::parse(query) {
+                Ok(filter) => Transaction::show(
+                    &view.transactions(self),
+                    &filter,
+                    "===== TRANSACTIONS =====",
+                    output,
+                ),
+                Err(e) => cli::fail(&e),
+            }
+        } else if arg == Data::DATA_TYPES[0] {
+            self.display(DataType::Account, view, output);
+        } else if arg == Data::DATA_TYPES[1] {
+            self.display(DataType::Category, view, output);
+        } else if arg == Data::DATA_TYPES[2] {
+            self.display(DataType::Transaction, view, output);
+        } else if arg == Data::DATA_TYPES[3] {
+            self.display(DataType::Rule, view, output);
+        } else if arg == Data::DATA_TYPES[4] {
+            self.display(DataType::Payee, view, output);
+        } else {
+            cli::fail(&format!("Unknown type: {}", arg));
         }
     }
 
-    pub fn search(&self, arg: &String) {
+    pub fn search(&self, arg: &str, view: &View) {
         // expect args to have a type argument
         if arg.is_empty() {
             return;
         }
-        Transaction::search(&self.transactions, arg);
+        match Output::take(arg) {
+            Ok((output, query)) => Transaction::search(&view.transactions(self), &query, output),
+            Err(e) => cli::fail(&e),
+        }
     }
 
     /* require mutable Data */
@@ -110,12 +300,34 @@ impl Data {
         if arg.is_empty() {
             return;
         }
-        if arg == &Data::DATA_TYPES[0] {
+        if arg == Data::DATA_TYPES[0] {
+            cli::name_positional(&Account::FIELDS);
             self.accounts.push(Account::new());
-        } else if arg == &Data::DATA_TYPES[1] {
+        } else if arg == Data::DATA_TYPES[1] {
+            cli::name_positional(&Category::FIELDS);
             self.categories.push(Category::new());
-        } else if arg == &Data::DATA_TYPES[2] {
-            self.transactions.push(Transaction::new());
+        } else if arg == Data::DATA_TYPES[2] {
+            cli::name_positional(&Transaction::FIELDS);
+            let mut tra =
+                Transaction::new(&mut self.payees, &mut self.accounts, &mut self.categories);
+            if let Some(index) = self.categorize(&mut tra) {
+                note!(
+                    "Rule {} applied: {}",
+                    self.rules[index].get_simple_id(),
+                    tra
+                );
+            }
+            self.push_checked(tra, &mut duplicate::Policy::Ask);
+        } else if arg == Data::DATA_TYPES[3] {
+            cli::name_positional(&Rule::FIELDS);
+            if let Some(rule) = Rule::new() {
+                self.rules.push(rule);
+            }
+        } else if arg == Data::DATA_TYPES[4] {
+            cli::name_positional(&Payee::FIELDS);
+            self.payees.push(Payee::new());
+        } else {
+            cli::fail(&format!("Unknown type: {}", arg));
         }
     }
 
@@ -123,50 +335,80 @@ impl Data {
         if arg.is_empty() {
             return;
         }
-        if arg == &Data::DATA_TYPES[0] {
+        cli::name_positional(&["id"]);
+        let fields = cli::edit_fields();
+        if arg == Data::DATA_TYPES[0] {
             let index = Account::find(&self.accounts);
             if index >= 0 {
                 if let Some(acc) = self.accounts.get_mut(index as usize) {
-                    println!("{}", acc);
-                    match acc.edit() {
-                        account::AccountField::Name(old, new) => {
-                            for tr in self.transactions.iter_mut() {
-                                if tr.get_account() == old {
-                                    tr.set_account(new.clone());
-                                }
+                    note!("{}", acc);
+                    for change in cli::for_each_field(&fields, |field| acc.edit(field)) {
+                        match change {
+                            account::AccountField::Name(old, new) => {
+                                rename_account(
+                                    &mut self.transactions,
+                                    &mut self.rules,
+                                    &mut self.payees,
+                                    &old,
+                                    &new,
+                                );
                             }
+                            account::AccountField::Value => (),
+                            account::AccountField::None => (),
                         }
-                        account::AccountField::Value => (),
-                        account::AccountField::None => (),
                     }
                 }
             }
-        } else if arg == &Data::DATA_TYPES[1] {
+        } else if arg == Data::DATA_TYPES[1] {
             let index = Category::find(&self.categories);
             if index >= 0 {
                 if let Some(cat) = self.categories.get_mut(index as usize) {
-                    println!("{}", cat);
-                    match cat.edit() {
-                        category::CategoryField::Name(old, new) => {
-                            for tr in self.transactions.iter_mut() {
-                                if tr.get_category() == old {
-                                    tr.set_category(new.clone());
-                                }
+                    note!("{}", cat);
+                    for change in cli::for_each_field(&fields, |field| cat.edit(field)) {
+                        match change {
+                            category::CategoryField::Name(old, new) => {
+                                rename_category(
+                                    &mut self.transactions,
+                                    &mut self.rules,
+                                    &mut self.payees,
+                                    &old,
+                                    &new,
+                                );
                             }
+                            category::CategoryField::Expected => (),
+                            category::CategoryField::None => (),
                         }
-                        category::CategoryField::Expected => (),
-                        category::CategoryField::None => (),
                     }
                 }
             }
-        } else if arg == &Data::DATA_TYPES[2] {
+        } else if arg == Data::DATA_TYPES[2] {
             let index = Transaction::find(&self.transactions);
+            let accounts = &mut self.accounts;
+            let categories = &mut self.categories;
             if index >= 0 {
                 if let Some(tra) = self.transactions.get_mut(index as usize) {
-                    println!("{}", tra);
-                    tra.edit();
+                    note!("{}", tra);
+                    cli::for_each_field(&fields, |field| tra.edit(field, accounts, categories));
+                }
+            }
+        } else if arg == Data::DATA_TYPES[3] {
+            let index = Rule::find(&self.rules);
+            if index >= 0 {
+                if let Some(rule) = self.rules.get_mut(index as usize) {
+                    note!("{}", rule);
+                    cli::for_each_field(&fields, |field| rule.edit(field));
                 }
             }
+        } else if arg == Data::DATA_TYPES[4] {
+            let index = Payee::find(&self.payees);
+            if index >= 0 {
+                if let Some(payee) = self.payees.get_mut(index as usize) {
+                    note!("{}", payee);
+                    cli::for_each_field(&fields, |field| payee.edit(field));
+                }
+            }
+        } else {
+            cli::fail(&format!("Unknown type: {}", arg));
         }
     }
 
@@ -174,7 +416,8 @@ impl Data {
         if arg.is_empty() {
             return;
         }
-        if arg == &Data::DATA_TYPES[0] {
+        cli::name_positional(&["id"]);
+        if arg == Data::DATA_TYPES[0] {
             let index = Account::find(&self.accounts);
             if index >= 0 {
                 let deleted = self.accounts.get(index as usize).unwrap();
@@ -185,7 +428,7 @@ impl Data {
                 }
                 self.accounts.remove(index as usize);
             }
-        } else if arg == &Data::DATA_TYPES[1] {
+        } else if arg == Data::DATA_TYPES[1] {
             let index = Category::find(&self.categories);
             if index >= 0 {
                 let deleted = self.categories.get(index as usize).unwrap();
@@ -196,12 +439,178 @@ impl Data {
                 }
                 self.categories.remove(index as usize);
             }
-        } else if arg == &Data::DATA_TYPES[2] {
+        } else if arg == Data::DATA_TYPES[2] {
             let index = Transaction::find(&self.transactions);
             if index >= 0 {
                 self.transactions.remove(index as usize);
             }
+        } else if arg == Data::DATA_TYPES[3] {
+            let index = Rule::find(&self.rules);
+            if index >= 0 {
+                self.rules.remove(index as usize);
+            }
+        } else if arg == Data::DATA_TYPES[4] {
+            let index = Payee::find(&self.payees);
+            if index >= 0 {
+                let deleted = self.payees.remove(index as usize);
+                for tr in self.transactions.iter_mut() {
+                    if tr.get_payee() == deleted.get_id() {
+                        tr.set_payee(String::new());
+                    }
+                }
+            }
+        } else {
+            cli::fail(&format!("Unknown type: {}", arg));
+        }
+    }
+
+    /// fold one Account or Category into another
+    ///     - Transactions, Rules and Payees naming the merged one are moved to the other
+    ///     - Category expected amounts are added together if the user chooses to
+    pub fn merge_into(&mut self, arg: &str) {
+        if arg.is_empty() {
+            return;
+        }
+        cli::name_positional(&["merge-id", "into-id"]);
+        let (from, into, moved) = if arg == Data::DATA_TYPES[0] {
+            let from = Account::find_as(&self.accounts, "Merge ID");
+            if from < 0 {
+                return;
+            }
+            let into = Account::find_as(&self.accounts, "Into ID");
+            if into < 0 {
+                return;
+            } else if into == from {
+                cli::fail("Can't merge an Account into itself");
+                return;
+            }
+            let merged = self.accounts.remove(from as usize);
+            let into = &mut self.accounts[if into > from { into - 1 } else { into } as usize];
+            into.set_value(cli::money_round(into.get_value() + merged.get_value()));
+            let moved = rename_account(
+                &mut self.transactions,
+                &mut self.rules,
+                &mut self.payees,
+                merged.get_name(),
+                into.get_name(),
+            );
+            (
+                merged.get_name().to_string(),
+                into.get_name().to_string(),
+                moved,
+            )
+        } else if arg == Data::DATA_TYPES[1] {
+            let from = Category::find_as(&self.categories, "Merge ID");
+            if from < 0 {
+                return;
+            }
+            let into = Category::find_as(&self.categories, "Into ID");
+            if into < 0 {
+                return;
+            } else if into == from {
+                cli::fail("Can't merge a Category into itself");
+                return;
+            }
+            let combine = cli::get_input("Combine expected amounts (y/n)");
+            let merged = self.categories.remove(from as usize);
+            let into = &mut self.categories[if into > from { into - 1 } else { into } as usize];
+            if combine == "y" || combine == "yes" {
+                into.set_expected(cli::money_round(
+                    into.get_expected() + merged.get_expected(),
+                ));
+            }
+            into.set_actual(cli::money_round(into.get_actual() + merged.get_actual()));
+            let moved = rename_category(
+                &mut self.transactions,
+                &mut self.rules,
+                &mut self.payees,
+                merged.get_name(),
+                into.get_name(),
+            );
+            (
+                merged.get_name().to_string(),
+                into.get_name().to_string(),
+                moved,
+            )
+        } else {
+            cli::fail(&format!("Only acc and cat can be merged, not {}", arg));
+            return;
+        };
+        note!("Merged {} into {}", from, into);
+        note!(
+            "Moved {} transactions, {} rules and {} payees",
+            moved[0],
+            moved[1],
+            moved[2]
+        );
+    }
+
+    /// change a field of every Transaction matching a filter, after previewing the result
+    ///     - expects args of the form `<filter> [set field=value...]`, without `set` the
+    ///       change is asked for
+    pub fn bulk(&mut self, arg: &str, view: &View) {
+        let (query, set) = match arg.split_once(" set ") {
+            Some((query, set)) => (query, set),
+            None => (arg, ""),
+        };
+        if query.trim().is_empty() {
+            cli::fail("Expected a filter, e.g. `b desc~coffee set category=Coffee`");
+            return;
+        }
+        let filter = match Filter::parse(query) {
+            Ok(filter) => filter,
+            Err(e) => {
+                cli::fail(&e);
+                return;
+            }
+        };
+        let matched: Vec<&Transaction> = view
+            .transactions(self)
+            .into_iter()
+            .filter(|tra| filter.matches(tra))
+            .collect();
+        if matched.is_empty() {
+            note!("No transactions match {}", query);
+            return;
+        }
+        let changes = if set.is_empty() {
+            Change::ask().map(|change| vec![change])
+        } else {
+            Change::parse_all(set)
+        };
+        let changes = match changes {
+            Ok(changes) => changes,
+            Err(e) => {
+                cli::fail(&e);
+                return;
+            }
+        };
+        bulk::preview(&matched, &changes);
+        let count = matched.len();
+        let apply = cli::get_input(&format!("Apply to {} transactions (y/n)", count));
+        if apply != "y" && apply != "yes" {
+            note!("Nothing changed");
+            return;
+        }
+        for tra in self.transactions.iter_mut() {
+            if filter.matches(tra) {
+                for change in &changes {
+                    change.apply(tra);
+                }
+            }
+        }
+        for change in &changes {
+            match change {
+                Change::Category(name) if self.ensure_category(name) => {
+                    note!("Added Category {}", name)
+                }
+                Change::Account(name) if self.ensure_account(name) => {
+                    note!("Added Account {}", name)
+                }
+                _ => (),
+            }
         }
+        note!("Changed {} transactions", count);
     }
 
     pub fn update(&mut self) {
@@ -223,7 +632,6 @@ impl Data {
             };
             category_map.insert(cat_name, cat_value);
         }
-        println!("{:?}", category_map);
         // iterate through Accounts and update Value fields
         for acc in self.accounts.iter_mut() {
             match account_map.get(acc.get_name()) {
@@ -240,9 +648,83 @@ impl Data {
         }
     }
 
+    /// list suspected duplicate pairs and offer to merge them
+    ///     - arg is the number of days apart two Transactions may be, 3 if empty
+    pub fn dups(&mut self, arg: &str) {
+        let days = if arg.is_empty() {
+            duplicate::DEFAULT_DAYS
+        } else {
+            match arg.parse() {
+                Ok(days) => days,
+                Err(e) => {
+                    cli::fail(&format!("Error reading number of days: {}", e));
+                    return;
+                }
+            }
+        };
+        loop {
+            let pairs = duplicate::find_pairs(&self.transactions, days);
+            if pairs.is_empty() {
+                note!("No suspected duplicates");
+                return;
+            }
+            duplicate::display(&self.transactions, &pairs);
+            note!("Merging keeps the first row of a pair, `d tra` deletes either one");
+            let choice = cli::get_input("Pair to merge (blank to finish)");
+            if choice.is_empty() {
+                return;
+            }
+            match choice.parse::<usize>() {
+                Ok(n) if n >= 1 && n <= pairs.len() => {
+                    let (keep, drop) = pairs[n - 1];
+                    let dropped = self.transactions.remove(drop);
+                    self.transactions[keep].merge_from(&dropped);
+                    note!("Merged into {}", self.transactions[keep]);
+                }
+                _ => cli::fail(&format!("No pair {}", choice)),
+            }
+        }
+    }
+
+    /// apply the Rules to every `<empty>` Transaction and list what changed
+    pub fn apply_rules(&mut self) {
+        let mut contents = Vec::new();
+        for i in 0..self.transactions.len() {
+            let mut tra = self.transactions[i].clone();
+            if let Some(index) = self.categorize(&mut tra) {
+                let mut row = vec![Content::St(self.rules[index].get_simple_id())];
+                row.extend(tra.to_row());
+                contents.push(row);
+                self.transactions[i] = tra;
+            }
+        }
+        note!("===== CATEGORIZED {} =====", contents.len());
+        let mut headers = vec!["rule"];
+        headers.extend(Transaction::HEADERS.iter());
+        cli::make_table(headers, &contents);
+    }
+
+    /// show which Rule matches each Transaction, without changing anything
+    pub fn match_rules(&self) {
+        let mut contents = Vec::new();
+        for tra in &self.transactions {
+            let (id, pattern) = match self.rules.iter().find(|rule| rule.matches(tra)) {
+                Some(rule) => (rule.get_simple_id(), rule.get_pattern().to_string()),
+                None => (String::from("<empty>"), String::new()),
+            };
+            let mut row = vec![Content::St(id), Content::St(pattern)];
+            row.extend(tra.to_row());
+            contents.push(row);
+        }
+        note!("===== RULE MATCHES =====");
+        let mut headers = vec!["rule", "pattern"];
+        headers.extend(Transaction::HEADERS.iter());
+        cli::make_table(headers, &contents);
+    }
+
     pub fn roll(&mut self, _arg: &String) {} // TODO:
 
-    pub fn to_cls(&self, path: &String) -> String {
+    pub fn to_cls(&self, path: &str) -> String {
         if path.ends_with("Account.cls") {
             let mut accounts = String::new();
             for acc in &self.accounts {
@@ -261,78 +743,355 @@ impl Data {
                 transactions.push_str(&Transaction::to_cls(tra));
             }
             transactions
+        } else if path.ends_with("Rule.cls") {
+            let mut rules = String::new();
+            for rule in &self.rules {
+                rules.push_str(&Rule::to_cls(rule));
+            }
+            rules
+        } else if path.ends_with("Payee.cls") {
+            let mut payees = String::new();
+            for payee in &self.payees {
+                payees.push_str(&Payee::to_cls(payee));
+            }
+            payees
         } else {
             eprintln!("Unexpected filename while writing to cls");
             String::new()
         }
     }
 
-    /// display the list of DataType
-    pub fn display(&self, data: DataType) {
+    /// everything that is saved, to tell whether a command changed anything
+    pub fn fingerprint(&self) -> String {
+        [
+            "Account.cls",
+            "Category.cls",
+            "Transaction.cls",
+            "Rule.cls",
+            "Payee.cls",
+        ]
+        .iter()
+        .map(|file| self.to_cls(file))
+        .collect()
+    }
+
+    /// display the list of DataType, in the order of view
+    pub fn display(&self, data: DataType, view: &View, output: Output) {
         match data {
             DataType::Account => {
                 let mut contents = Vec::new();
                 let mut total_value = 0.0;
-                for acc in self.accounts.iter() {
+                for acc in view.accounts(self) {
                     total_value += acc.get_value();
-                    contents.push({
-                        let mut tmp = Vec::new();
-                        tmp.push(Content::St(acc.get_simple_id()));
-                        tmp.push(Content::St(acc.get_name().to_string()));
-                        tmp.push(Content::Num(acc.get_value().to_string()));
-                        tmp
-                    });
+                    contents.push(vec![
+                        Content::St(acc.get_simple_id()),
+                        Content::St(acc.get_name().to_string()),
+                        Content::Toned(
+                            acc.get_value().to_string(),
+                            settings::account_tone(*acc.get_value()),
+                        ),
+                    ]);
+                }
+                Report {
+                    kind: "acc",
+                    title: "===== ACCOUNTS =====",
+                    headers: Account::HEADERS.to_vec(),
+                    contents,
+                    totals: vec![("worth", total_value)],
+                    summary: format!("You are worth ${}", cli::money_round(total_value)),
+                    summary_below: false,
                 }
-                println!("===== ACCOUNTS =====");
-                println!("You are worth ${}", cli::money_round(total_value));
-                cli::make_table(vec!["id", "name", "value"], &contents);
+                .print(output);
             }
             DataType::Category => {
                 let mut contents = Vec::new();
                 let mut total_expected = 0.0;
                 let mut total_actual = 0.0;
-                for cat in self.categories.iter() {
+                for cat in view.categories(self) {
                     if cat.get_name() != "Rollover" {
                         total_expected += cat.get_expected();
                         total_actual += cat.get_actual();
                     }
-                    contents.push({
-                        let mut tmp = Vec::new();
-                        tmp.push(Content::St(cat.get_simple_id()));
-                        tmp.push(Content::St(cat.get_name().to_string()));
-                        tmp.push(Content::Num(cat.get_expected().to_string()));
-                        tmp.push(Content::Num(cat.get_actual().to_string()));
-                        tmp
-                    });
+                    contents.push(vec![
+                        Content::St(cat.get_simple_id()),
+                        Content::St(cat.get_name().to_string()),
+                        Content::Num(cat.get_expected().to_string()),
+                        Content::Toned(
+                            cat.get_actual().to_string(),
+                            settings::category_tone(*cat.get_expected(), *cat.get_actual()),
+                        ),
+                    ]);
                 }
-                println!("===== CATEGORIES =====");
-                println!(
-                    "You had planned to save ${}, you are actually saving ${}",
-                    cli::money_round(total_expected),
-                    cli::money_round(total_actual)
-                );
-                cli::make_table(vec!["id", "name", "expected", "actual"], &contents);
+                Report {
+                    kind: "cat",
+                    title: "===== CATEGORIES =====",
+                    headers: Category::HEADERS.to_vec(),
+                    contents,
+                    totals: vec![("expected", total_expected), ("actual", total_actual)],
+                    summary: format!(
+                        "You had planned to save ${}, you are actually saving ${}",
+                        cli::money_round(total_expected),
+                        cli::money_round(total_actual)
+                    ),
+                    summary_below: false,
+                }
+                .print(output);
             }
-            DataType::Transaction => {
+            DataType::Transaction => Transaction::show(
+                &view.transactions(self),
+                &Filter::All,
+                "===== TRANSACTIONS =====",
+                output,
+            ),
+            DataType::Rule => {
                 let mut contents = Vec::new();
-                for tra in self.transactions.iter() {
-                    contents.push({
-                        let mut tmp = Vec::new();
-                        tmp.push(Content::St(tra.get_simple_id()));
-                        tmp.push(Content::St(tra.get_date()));
-                        tmp.push(Content::Num(tra.get_amount().to_string()));
-                        tmp.push(Content::St(tra.get_account().to_string()));
-                        tmp.push(Content::St(tra.get_category().to_string()));
-                        tmp.push(Content::St(tra.get_description().to_string()));
-                        tmp
-                    });
+                for rule in self.rules.iter() {
+                    contents.push(rule.to_row());
                 }
-                println!("===== TRANSACTIONS =====");
-                cli::make_table(
-                    vec!["id", "date", "amount", "account", "category", "description"],
-                    &contents,
-                );
+                Report {
+                    kind: "rul",
+                    title: "===== RULES =====",
+                    headers: Rule::HEADERS.to_vec(),
+                    contents,
+                    totals: Vec::new(),
+                    summary: String::from(
+                        "The first matching rule applies to uncategorized transactions",
+                    ),
+                    summary_below: false,
+                }
+                .print(output);
             }
+            DataType::Payee => {
+                let mut contents = Vec::new();
+                for payee in self.payees.iter() {
+                    let used = self
+                        .transactions
+                        .iter()
+                        .filter(|tra| tra.get_payee() == payee.get_id())
+                        .count();
+                    let mut row = payee.to_row();
+                    row.push(Content::Num(used.to_string()));
+                    contents.push(row);
+                }
+                let mut headers = Payee::HEADERS.to_vec();
+                headers.push("this month");
+                Report {
+                    kind: "pay",
+                    title: "===== PAYEES =====",
+                    headers,
+                    contents,
+                    totals: Vec::new(),
+                    summary: String::new(),
+                    summary_below: false,
+                }
+                .print(output);
+            }
+        }
+    }
+}
+
+/// point everything naming Account old at new instead
+///     - returns how many Transactions, Rules and Payees changed
+fn rename_account(
+    transactions: &mut [Transaction],
+    rules: &mut [Rule],
+    payees: &mut [Payee],
+    old: &str,
+    new: &str,
+) -> [usize; 3] {
+    let mut moved = [0; 3];
+    for tr in transactions.iter_mut() {
+        if tr.get_account() == old {
+            tr.set_account(new.to_string());
+            moved[0] += 1;
+        }
+    }
+    for rule in rules.iter_mut() {
+        let mut changed = false;
+        if rule.get_account() == old {
+            rule.set_account(new.to_string());
+            changed = true;
+        }
+        if rule.get_match_account() == old {
+            rule.set_match_account(new.to_string());
+            changed = true;
+        }
+        moved[1] += usize::from(changed);
+    }
+    for payee in payees.iter_mut() {
+        if payee.get_account() == old {
+            payee.set_account(new.to_string());
+            moved[2] += 1;
+        }
+    }
+    moved
+}
+
+/// point everything naming Category old at new instead
+///     - returns how many Transactions, Rules and Payees changed
+fn rename_category(
+    transactions: &mut [Transaction],
+    rules: &mut [Rule],
+    payees: &mut [Payee],
+    old: &str,
+    new: &str,
+) -> [usize; 3] {
+    let mut moved = [0; 3];
+    for tr in transactions.iter_mut() {
+        if tr.get_category() == old {
+            tr.set_category(new.to_string());
+            moved[0] += 1;
         }
     }
+    for rule in rules.iter_mut() {
+        if rule.get_category() == old {
+            rule.set_category(new.to_string());
+            moved[1] += 1;
+        }
+    }
+    for payee in payees.iter_mut() {
+        if payee.get_category() == old {
+            payee.set_category(new.to_string());
+            moved[2] += 1;
+        }
+    }
+    moved
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn answer(fields: &[(&str, &str)]) {
+        cli::add_named(
+            fields
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        );
+        cli::set_strict(true);
+    }
+
+    fn month() -> Data {
+        let mut data = Data::new();
+        data.accounts = vec![
+            Account::from_cls(Some("Checking"), Some("100"), None),
+            Account::from_cls(Some("Old Checking"), Some("25.5"), None),
+        ];
+        data.categories = vec![
+            Category::from_cls(Some("Food"), Some("-300"), Some("-40"), None),
+            Category::from_cls(Some("Dining"), Some("-100"), Some("-12.5"), None),
+        ];
+        for (account, category) in [("Old Checking", "Dining"), ("Checking", "Food")] {
+            data.transactions.push(Transaction::from_parts(
+                Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap(),
+                -10.0,
+                account.to_string(),
+                category.to_string(),
+                String::from("Lunch"),
+            ));
+        }
+        data.rules = vec![Rule::from_cls(
+            Some("cafe"),
+            None,
+            None,
+            Some("Old Checking"),
+            Some("Dining"),
+            None,
+            None,
+            None,
+        )];
+        data.payees = vec![Payee::from_cls(
+            None,
+            Some("Cafe"),
+            None,
+            Some("Dining"),
+            Some("Old Checking"),
+        )];
+        data
+    }
+
+    #[test]
+    fn merge_accounts() {
+        let mut data = month();
+        let from = data.accounts[1].get_simple_id();
+        let into = data.accounts[0].get_simple_id();
+        answer(&[("merge-id", &from), ("into-id", &into)]);
+        data.merge_into("acc");
+        cli::clear_answers();
+        assert!(!cli::take_failure());
+        assert_eq!(data.accounts.len(), 1);
+        assert_eq!(*data.accounts[0].get_value(), 125.5);
+        assert!(data
+            .transactions
+            .iter()
+            .all(|tra| tra.get_account() == "Checking"));
+        assert_eq!(data.rules[0].get_match_account(), "Checking");
+        assert_eq!(data.payees[0].get_account(), "Checking");
+    }
+
+    #[test]
+    fn merge_categories_combining_expected() {
+        let mut data = month();
+        let from = data.categories[1].get_simple_id();
+        let into = data.categories[0].get_simple_id();
+        answer(&[
+            ("merge-id", &from),
+            ("into-id", &into),
+            ("combine-expected-amounts", "y"),
+        ]);
+        data.merge_into("cat");
+        cli::clear_answers();
+        assert!(!cli::take_failure());
+        assert_eq!(data.categories.len(), 1);
+        assert_eq!(*data.categories[0].get_expected(), -400.0);
+        assert_eq!(*data.categories[0].get_actual(), -52.5);
+        assert!(data
+            .transactions
+            .iter()
+            .all(|tra| tra.get_category() == "Food"));
+        assert_eq!(data.rules[0].get_category(), "Food");
+        assert_eq!(data.payees[0].get_category(), "Food");
+    }
+
+    #[test]
+    fn merge_into_itself_fails() {
+        let mut data = month();
+        let id = data.accounts[0].get_simple_id();
+        answer(&[("merge-id", &id), ("into-id", &id)]);
+        data.merge_into("acc");
+        cli::clear_answers();
+        assert!(cli::take_failure());
+        assert_eq!(data.accounts.len(), 2);
+    }
+
+    #[test]
+    fn bulk_edits_matching_transactions() {
+        let mut data = month();
+        answer(&[("apply-to-1-transactions", "y")]);
+        data.bulk("acc=\"Old Checking\" set category=Treats", &View::new());
+        cli::clear_answers();
+        assert!(!cli::take_failure());
+        let categories: Vec<&str> = data
+            .transactions
+            .iter()
+            .map(|tra| tra.get_category())
+            .collect();
+        assert_eq!(categories, vec!["Treats", "Food"]);
+        assert!(data.categories.iter().any(|cat| cat.get_name() == "Treats"));
+    }
+
+    #[test]
+    fn bulk_edits_nothing_unless_confirmed() {
+        let mut data = month();
+        answer(&[("apply-to-2-transactions", "n")]);
+        data.bulk("desc~lunch set account=Cash", &View::new());
+        cli::clear_answers();
+        assert!(data
+            .transactions
+            .iter()
+            .all(|tra| tra.get_account() != "Cash"));
+        assert_eq!(data.accounts.len(), 2);
+    }
 }
@@ -1,4 +1,5 @@
 use crate::cli;
+use crate::view::SortValue;
 use std::fmt;
 use uuid::{adapter::Simple, Uuid};
 
@@ -16,6 +17,15 @@ pub struct Account {
 }
 
 impl Account {
+    /// the prompts answered by the words after `a acc`, in order
+    pub const FIELDS: [&'static str; 1] = ["name"];
+
+    /// column headers of the accounts table
+    pub const HEADERS: [&'static str; 3] = ["id", "name", "value"];
+
+    /// the fields `s acc` can sort by
+    pub const SORT_FIELDS: [&'static str; 2] = ["name", "value"];
+
     pub fn get_simple_id(&self) -> String {
         self.simplify_id()
     }
@@ -35,18 +45,19 @@ impl Account {
         self.value = new_value;
     }
 
-    pub fn from_cls(possible_name: Option<&str>, possible_value: Option<&str>) -> Account {
+    pub fn from_cls(
+        possible_name: Option<&str>,
+        possible_value: Option<&str>,
+        possible_id: Option<&str>,
+    ) -> Account {
         Account {
-            id: Uuid::new_v4(),
+            id: cli::id_from_cls(possible_id),
             name: match possible_name {
                 Some(name) => String::from(name),
                 None => String::new(),
             },
             value: match possible_value {
-                Some(value) => match str::parse(value) {
-                    Ok(parsed) => parsed,
-                    Err(_) => 0.0,
-                },
+                Some(value) => str::parse(value).unwrap_or(0.0),
                 None => 0.0,
             },
         }
@@ -61,32 +72,54 @@ impl Account {
         }
     }
 
-    pub fn find(accounts: &Vec<Account>) -> i32 {
-        let arg = cli::get_input("ID");
-        let mut index = 0;
-        for acc in accounts {
+    /// the Account name to use for an entered one, adding the Account if the user chooses to
+    ///     - None if the name failed, see `cli::check_name`
+    pub fn check(accounts: &mut Vec<Account>, name: String) -> Option<String> {
+        let known: Vec<&str> = accounts.iter().map(|x| x.get_name()).collect();
+        let (name, create) = cli::check_name("Account", name, &known)?;
+        if create {
+            accounts.push(Account::from_cls(Some(&name), None, None));
+        }
+        Some(name)
+    }
+
+    pub fn find(accounts: &[Account]) -> i32 {
+        Account::find_as(accounts, "ID")
+    }
+
+    /// find by the ID answered to the prompt label, for commands that need more than one
+    pub fn find_as(accounts: &[Account], label: &str) -> i32 {
+        let arg = cli::get_input(label);
+        for (index, acc) in accounts.iter().enumerate() {
             if acc.simplify_id().contains(&arg) {
-                return index;
+                return index as i32;
             }
-            index += 1;
         }
+        cli::fail(&format!("No Account with ID {}", arg));
         -1
     }
 
-    pub fn edit(&mut self) -> AccountField {
-        let field = cli::get_input("Field to edit");
+    pub fn edit(&mut self, field: &str) -> AccountField {
         if field == "name" {
             let tmp = self.get_name().to_string();
             self.set_name(cli::get_input("Name"));
             AccountField::Name(tmp, self.get_name().to_string())
         } else if field == "value" {
-            self.value = cli::try_into_money(&mut cli::get_input("Value"));
+            self.value = cli::try_into_money(&cli::get_input("Value"));
             AccountField::Value
         } else {
+            cli::fail(&format!("Unknown field: {}", field));
             AccountField::None
         }
     }
 
+    pub fn sort_value(&self, field: &str) -> SortValue {
+        match field {
+            "value" => SortValue::Num(self.value as f64),
+            _ => SortValue::Text(self.name.clone()),
+        }
+    }
+
     fn simplify_id(&self) -> String {
         let id = Simple::from_uuid(self.id);
         let mut id = id.to_string();
@@ -96,9 +129,11 @@ impl Account {
 
     pub fn to_cls(&self) -> String {
         let mut st = String::new();
-        st.push_str(&self.name);
+        st.push_str(&cli::escape_cls(&self.name));
         st.push(',');
         st.push_str(&self.value.to_string());
+        st.push(',');
+        st.push_str(&self.id.to_string());
         st.push('\n');
         st
     }
@@ -0,0 +1,179 @@
+use crate::cli::{self, Content};
+use crate::data::transaction::Transaction;
+
+/// the fields `b` can change
+pub const FIELDS: [&str; 4] = ["category", "account", "tags", "description"];
+
+/// a change made to every Transaction a bulk edit matched
+pub enum Change {
+    Category(String),
+    Account(String),
+    Tags(Vec<String>),
+    Description(String),
+    /// replace the first text with the second in descriptions
+    Rewrite(String, String),
+}
+
+impl Change {
+    /// parse the `field=value` words after `set`
+    pub fn parse_all(set: &str) -> Result<Vec<Change>, String> {
+        let mut changes = Vec::new();
+        for word in cli::split_words(set) {
+            let (field, value) = match word.split_once('=') {
+                Some(parts) => parts,
+                None => return Err(format!("Expected field=value after set, not {}", word)),
+            };
+            changes.push(Change::from_field(field, value.to_string())?);
+        }
+        Ok(changes)
+    }
+
+    /// ask which field to change and how
+    pub fn ask() -> Result<Change, String> {
+        let field = cli::get_input("Field to change (category, account, tags, description)");
+        match field.as_str() {
+            "category" | "cat" => Change::from_field("category", cli::get_input("Category")),
+            "account" | "acc" => Change::from_field("account", cli::get_input("Account")),
+            "tags" | "tag" => Change::from_field("tags", cli::get_input("Tags to add")),
+            "description" | "desc" => {
+                let old = cli::get_input("Replace (blank for the whole description)");
+                let new = cli::get_input("With");
+                if old.is_empty() {
+                    Ok(Change::Description(new))
+                } else {
+                    Ok(Change::Rewrite(old, new))
+                }
+            }
+            _ => Err(format!(
+                "Can't bulk edit {}, expected one of: {}",
+                field,
+                FIELDS.join(", ")
+            )),
+        }
+    }
+
+    /// the change of field to value
+    ///     - a blank category or account is an error, `<empty>` clears it
+    fn from_field(field: &str, value: String) -> Result<Change, String> {
+        match field {
+            "category" | "cat" | "account" | "acc" if value.is_empty() => Err(format!(
+                "Can't set {} to nothing, use {}=<empty> to clear it",
+                field, field
+            )),
+            "category" | "cat" => Ok(Change::Category(value)),
+            "account" | "acc" => Ok(Change::Account(value)),
+            "tags" | "tag" => Ok(Change::Tags(
+                value
+                    .split([' ', ','])
+                    .filter(|tag| !tag.is_empty())
+                    .map(String::from)
+                    .collect(),
+            )),
+            "description" | "desc" => Ok(Change::Description(value)),
+            _ => Err(format!(
+                "Can't bulk edit {}, expected one of: {}",
+                field,
+                FIELDS.join(", ")
+            )),
+        }
+    }
+
+    /// the column of `Transaction::HEADERS` the change shows up in
+    fn column(&self) -> &'static str {
+        match self {
+            Change::Category(_) => "category",
+            Change::Account(_) => "account",
+            Change::Tags(_) => "tags",
+            Change::Description(_) | Change::Rewrite(..) => "description",
+        }
+    }
+
+    pub fn apply(&self, tra: &mut Transaction) {
+        match self {
+            Change::Category(category) => tra.set_category(category.clone()),
+            Change::Account(account) => tra.set_account(account.clone()),
+            Change::Tags(tags) => tra.add_tags(tags),
+            Change::Description(description) => tra.set_description(description.clone()),
+            Change::Rewrite(old, new) => {
+                let rewritten = tra.get_description().replace(old.as_str(), new);
+                tra.set_description(rewritten);
+            }
+        }
+    }
+}
+
+/// print the Transactions as they will be after the changes, with changed cells highlighted
+///     - every column is shown, whatever `cols` chose, so no change is hidden
+pub fn preview(transactions: &[&Transaction], changes: &[Change]) {
+    let mut contents = Vec::new();
+    for tra in transactions {
+        let mut after = (*tra).clone();
+        for change in changes {
+            change.apply(&mut after);
+        }
+        let before = tra.to_row();
+        let mut row = after.to_row();
+        for change in changes {
+            if let Some(column) = Transaction::HEADERS
+                .iter()
+                .position(|h| *h == change.column())
+            {
+                if before[column].text() != row[column].text() {
+                    let text = row[column].text().to_string();
+                    let len = text.len();
+                    row[column] = Content::Marked(text, vec![(0, len)]);
+                }
+            }
+        }
+        contents.push(row);
+    }
+    note!("===== BULK EDIT PREVIEW =====");
+    cli::make_table(Transaction::HEADERS.to_vec(), &contents);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn lunch() -> Transaction {
+        Transaction::from_parts(
+            Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap(),
+            -12.5,
+            String::from("Checking"),
+            String::from("<empty>"),
+            String::from("SQ *CORNER CAFE"),
+        )
+    }
+
+    #[test]
+    fn parse_changes() {
+        let changes = Change::parse_all(r#"cat=Dining acc="Visa Card" tags=work,lunch"#).unwrap();
+        let mut tra = lunch();
+        for change in &changes {
+            change.apply(&mut tra);
+        }
+        assert_eq!(tra.get_category(), "Dining");
+        assert_eq!(tra.get_account(), "Visa Card");
+        assert!(tra.to_cls().contains(",work lunch,"));
+    }
+
+    #[test]
+    fn rewrite_descriptions() {
+        let mut tra = lunch();
+        Change::Rewrite(String::from("SQ *"), String::new()).apply(&mut tra);
+        assert_eq!(tra.get_description(), "CORNER CAFE");
+        Change::Description(String::from("Lunch")).apply(&mut tra);
+        assert_eq!(tra.get_description(), "Lunch");
+    }
+
+    #[test]
+    fn bad_changes() {
+        assert!(Change::parse_all("category").is_err());
+        assert!(Change::parse_all("amount=5").is_err());
+        assert!(Change::parse_all("category=").is_err());
+        assert!(Change::parse_all("account=").is_err());
+        assert!(Change::parse_all("category=<empty>").is_ok());
+        assert!(Change::parse_all("description=").is_ok());
+    }
+}
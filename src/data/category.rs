@@ -1,4 +1,5 @@
 use crate::cli;
+use crate::view::SortValue;
 use std::fmt;
 use uuid::{adapter::Simple, Uuid};
 
@@ -17,6 +18,15 @@ pub struct Category {
 }
 
 impl Category {
+    /// the prompts answered by the words after `a cat`, in order
+    pub const FIELDS: [&'static str; 2] = ["name", "expected"];
+
+    /// column headers of the categories table
+    pub const HEADERS: [&'static str; 4] = ["id", "name", "expected", "actual"];
+
+    /// the fields `s cat` can sort by
+    pub const SORT_FIELDS: [&'static str; 3] = ["name", "expected", "actual"];
+
     pub fn get_simple_id(&self) -> String {
         self.simplify_id()
     }
@@ -33,6 +43,10 @@ impl Category {
         &self.expected
     }
 
+    pub fn set_expected(&mut self, new_expected: f32) {
+        self.expected = new_expected;
+    }
+
     pub fn get_actual(&self) -> &f32 {
         &self.actual
     }
@@ -45,25 +59,20 @@ impl Category {
         possible_name: Option<&str>,
         possible_expected: Option<&str>,
         possible_actual: Option<&str>,
+        possible_id: Option<&str>,
     ) -> Category {
         Category {
-            id: Uuid::new_v4(),
+            id: cli::id_from_cls(possible_id),
             name: match possible_name {
                 Some(name) => String::from(name),
                 None => String::new(),
             },
             expected: match possible_expected {
-                Some(expected) => match str::parse(expected) {
-                    Ok(parsed) => parsed,
-                    Err(_) => 0.0,
-                },
+                Some(expected) => str::parse(expected).unwrap_or(0.0),
                 None => 0.0,
             },
             actual: match possible_actual {
-                Some(actual) => match str::parse(actual) {
-                    Ok(parsed) => parsed,
-                    Err(_) => 0.0,
-                },
+                Some(actual) => str::parse(actual).unwrap_or(0.0),
                 None => 0.0,
             },
         }
@@ -71,8 +80,8 @@ impl Category {
 
     pub fn new() -> Category {
         let name = cli::get_input("Name");
-        let mut possible_expected = cli::get_input("Expected");
-        let expected = cli::try_into_money(&mut possible_expected);
+        let possible_expected = cli::get_input("Expected");
+        let expected = cli::try_into_money(&possible_expected);
         Category {
             id: Uuid::new_v4(),
             name,
@@ -81,32 +90,55 @@ impl Category {
         }
     }
 
-    pub fn find(categories: &Vec<Category>) -> i32 {
-        let arg = cli::get_input("ID");
-        let mut index = 0;
-        for cat in categories {
+    /// the Category name to use for an entered one, adding the Category if the user chooses to
+    ///     - None if the name failed, see `cli::check_name`
+    pub fn check(categories: &mut Vec<Category>, name: String) -> Option<String> {
+        let known: Vec<&str> = categories.iter().map(|x| x.get_name()).collect();
+        let (name, create) = cli::check_name("Category", name, &known)?;
+        if create {
+            categories.push(Category::from_cls(Some(&name), None, None, None));
+        }
+        Some(name)
+    }
+
+    pub fn find(categories: &[Category]) -> i32 {
+        Category::find_as(categories, "ID")
+    }
+
+    /// find by the ID answered to the prompt label, for commands that need more than one
+    pub fn find_as(categories: &[Category], label: &str) -> i32 {
+        let arg = cli::get_input(label);
+        for (index, cat) in categories.iter().enumerate() {
             if cat.simplify_id().contains(&arg) {
-                return index;
+                return index as i32;
             }
-            index += 1;
         }
+        cli::fail(&format!("No Category with ID {}", arg));
         -1
     }
 
-    pub fn edit(&mut self) -> CategoryField {
-        let field = cli::get_input("Field to edit");
+    pub fn edit(&mut self, field: &str) -> CategoryField {
         if field == "name" {
             let tmp = self.get_name().to_string();
             self.set_name(cli::get_input("Name"));
             CategoryField::Name(tmp, self.get_name().to_string())
         } else if field == "expected" {
-            self.expected = cli::try_into_money(&mut cli::get_input("Expected"));
+            self.expected = cli::try_into_money(&cli::get_input("Expected"));
             CategoryField::Expected
         } else {
+            cli::fail(&format!("Unknown field: {}", field));
             CategoryField::None
         }
     }
 
+    pub fn sort_value(&self, field: &str) -> SortValue {
+        match field {
+            "expected" => SortValue::Num(self.expected as f64),
+            "actual" => SortValue::Num(self.actual as f64),
+            _ => SortValue::Text(self.name.clone()),
+        }
+    }
+
     fn simplify_id(&self) -> String {
         let id = Simple::from_uuid(self.id);
         let mut id = id.to_string();
@@ -116,11 +148,13 @@ impl Category {
 
     pub fn to_cls(&self) -> String {
         let mut st = String::new();
-        st.push_str(&self.name);
+        st.push_str(&cli::escape_cls(&self.name));
         st.push(',');
         st.push_str(&self.expected.to_string());
         st.push(',');
         st.push_str(&self.actual.to_string());
+        st.push(',');
+        st.push_str(&self.id.to_string());
         st.push('\n');
         st
     }
@@ -0,0 +1,161 @@
+use crate::cli::{self, Content};
+use crate::data::transaction::Transaction;
+
+/// how far apart (in days) two Transactions can be and still count as duplicates
+pub const DEFAULT_DAYS: i64 = 3;
+
+/// what to do with a new Transaction that looks like one already in the month
+pub enum Policy {
+    Ask,
+    Add,
+    Skip,
+}
+
+impl Policy {
+    /// decide whether new should be added next to existing, asking when needed
+    ///     - `a`/`s` answers stick for the rest of an import
+    pub fn allows(&mut self, new: &Transaction, existing: &Transaction) -> bool {
+        match self {
+            Policy::Add => true,
+            Policy::Skip => false,
+            Policy::Ask => {
+                note!("Possible duplicate:");
+                note!("  new:      {}", new);
+                note!("  existing: {}", existing);
+                match cli::get_input("Add anyway? (y)es, (n)o, (a)dd all, (s)kip all").as_str() {
+                    "y" | "yes" => true,
+                    "a" => {
+                        *self = Policy::Add;
+                        true
+                    }
+                    "s" => {
+                        *self = Policy::Skip;
+                        false
+                    }
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+/// same account, same amount, dated within days of each other and a similar description
+///     - two different FITIDs (or other import ids) are never duplicates
+pub fn is_likely(a: &Transaction, b: &Transaction, days: i64) -> bool {
+    if !a.get_import_id().is_empty()
+        && !b.get_import_id().is_empty()
+        && a.get_import_id() != b.get_import_id()
+    {
+        return false;
+    }
+    a.get_account() == b.get_account()
+        && (a.get_amount() - b.get_amount()).abs() < 0.005
+        && (a.get_date_time() - b.get_date_time()).num_days().abs() <= days
+        && similar(a.get_description(), b.get_description())
+}
+
+/// every pair of likely duplicates in transactions, as indices
+pub fn find_pairs(transactions: &[Transaction], days: i64) -> Vec<(usize, usize)> {
+    let mut pairs = Vec::new();
+    for (i, a) in transactions.iter().enumerate() {
+        for (j, b) in transactions.iter().enumerate().skip(i + 1) {
+            if is_likely(a, b, days) {
+                pairs.push((i, j));
+            }
+        }
+    }
+    pairs
+}
+
+/// print the pairs as a table, numbered from 1
+pub fn display(transactions: &[Transaction], pairs: &[(usize, usize)]) {
+    let mut contents = Vec::new();
+    for (n, (i, j)) in pairs.iter().enumerate() {
+        for index in [i, j] {
+            let mut row = vec![Content::St((n + 1).to_string())];
+            row.extend(transactions[*index].to_row());
+            contents.push(row);
+        }
+    }
+    let mut headers = vec!["pair"];
+    headers.extend(Transaction::HEADERS.iter());
+    note!("===== SUSPECTED DUPLICATES =====");
+    cli::make_table(headers, &contents);
+}
+
+/// descriptions match when their words mostly overlap or one contains the other
+fn similar(a: &str, b: &str) -> bool {
+    let a_words = words(a);
+    let b_words = words(b);
+    if a_words.is_empty() || b_words.is_empty() {
+        return a_words.is_empty() && b_words.is_empty();
+    }
+    let a_joined = a_words.join(" ");
+    let b_joined = b_words.join(" ");
+    if a_joined.contains(&b_joined) || b_joined.contains(&a_joined) {
+        return true;
+    }
+    let shared = a_words.iter().filter(|w| b_words.contains(w)).count();
+    let total = a_words.len() + b_words.len() - shared;
+    shared * 2 >= total
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn tra(day: u32, amount: f32, account: &str, description: &str) -> Transaction {
+        Transaction::from_parts(
+            Utc.with_ymd_and_hms(2026, 10, day, 0, 0, 0).unwrap(),
+            amount,
+            account.to_string(),
+            String::from("<empty>"),
+            description.to_string(),
+        )
+    }
+
+    #[test]
+    fn likely_duplicates() {
+        let lunch = tra(10, -12.5, "Checking", "Corner Cafe");
+        let likely = |other: Transaction| is_likely(&lunch, &other, 3);
+        assert!(likely(tra(12, -12.5, "Checking", "CORNER CAFE #42")));
+        assert!(likely(tra(13, -12.5, "Checking", "corner cafe")));
+        assert!(!likely(tra(14, -12.5, "Checking", "Corner Cafe")));
+        assert!(!likely(tra(10, -12.0, "Checking", "Corner Cafe")));
+        assert!(!likely(tra(10, -12.5, "Card", "Corner Cafe")));
+        assert!(!likely(tra(10, -12.5, "Checking", "Hardware Store")));
+    }
+
+    #[test]
+    fn different_import_ids_are_not_duplicates() {
+        let mut a = tra(10, -3.0, "Checking", "Coffee");
+        let mut b = tra(10, -3.0, "Checking", "Coffee");
+        a.set_import_id(String::from("A1"));
+        assert!(is_likely(&a, &b, 3));
+        b.set_import_id(String::from("A2"));
+        assert!(!is_likely(&a, &b, 3));
+        b.set_import_id(String::from("A1"));
+        assert!(is_likely(&a, &b, 3));
+    }
+
+    #[test]
+    fn pairs_by_index() {
+        let transactions = vec![
+            tra(1, -9.99, "Card", "Streaming"),
+            tra(2, -40.0, "Card", "Fuel"),
+            tra(3, -9.99, "Card", "Streaming service"),
+            tra(20, -9.99, "Card", "Streaming"),
+        ];
+        assert_eq!(find_pairs(&transactions, DEFAULT_DAYS), vec![(0, 2)]);
+        assert_eq!(find_pairs(&transactions, 30), vec![(0, 2), (0, 3), (2, 3)]);
+    }
+}
@@ -0,0 +1,661 @@
+use crate::cli;
+use crate::data::transaction::Transaction;
+use crate::view::SortValue;
+use chrono::NaiveTime;
+use regex::{Regex, RegexBuilder};
+
+/// which Transactions a search, list or export should include
+///     - terms are `field<op>value`, e.g. `category=Food amount<-50 date>=10/01/2026 desc~coffee`
+///     - terms next to each other must all match, `or` and `not` and parentheses combine them
+///     - a word without an operator matches date, account, category or description containing it
+///     - leading `-i` ignores case, `-r` makes text values regexes and `-f` matches them fuzzily
+pub enum Filter {
+    All,
+    Term(Term),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+/// one `field<op>value` comparison, or a bare word when field is None
+pub struct Term {
+    field: Option<&'static str>,
+    op: Op,
+    value: SortValue,
+    ignore_case: bool,
+    pattern: Option<Pattern>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Op {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    Excludes,
+}
+
+/// how `~` and bare words look for their value
+#[derive(Clone, Copy, PartialEq)]
+enum Mode {
+    Plain,
+    Regex,
+    Fuzzy,
+}
+
+/// the compiled value of a `~` term or bare word
+enum Pattern {
+    Regex(Regex),
+    Fuzzy(Vec<char>),
+}
+
+/// where a pattern matched a field: how well, and the byte ranges to highlight
+struct Found {
+    score: i64,
+    ranges: Vec<(usize, usize)>,
+}
+
+/// operators in the order they are tried, longest first
+const OPS: [(&str, Op); 8] = [
+    ("<=", Op::Le),
+    (">=", Op::Ge),
+    ("!=", Op::Ne),
+    ("!~", Op::Excludes),
+    ("<", Op::Lt),
+    (">", Op::Gt),
+    ("=", Op::Eq),
+    ("~", Op::Contains),
+];
+
+/// the fields a term can name, with their short forms
+const FIELDS: [(&str, &str); 10] = [
+    ("date", "date"),
+    ("amount", "amount"),
+    ("amt", "amount"),
+    ("account", "account"),
+    ("acc", "account"),
+    ("category", "category"),
+    ("cat", "category"),
+    ("description", "description"),
+    ("desc", "description"),
+    ("tags", "tags"),
+];
+
+/// the fields a bare word looks in
+const BARE_FIELDS: [&str; 4] = ["date", "account", "category", "description"];
+
+impl Filter {
+    /// parse a query, an empty one matches everything
+    pub fn parse(query: &str) -> Result<Filter, String> {
+        let mut tokens = tokenize(query);
+        let mut ignore_case = false;
+        let mut mode = Mode::Plain;
+        while let Some(flags) = tokens.first().and_then(|token| option_letters(token)) {
+            for flag in flags.chars() {
+                match flag {
+                    'i' => ignore_case = true,
+                    'r' if mode != Mode::Fuzzy => mode = Mode::Regex,
+                    'f' if mode != Mode::Regex => mode = Mode::Fuzzy,
+                    _ => return Err(String::from("Use either -r or -f, not both")),
+                }
+            }
+            tokens.remove(0);
+        }
+        if tokens.is_empty() {
+            return Ok(Filter::All);
+        }
+        let mut parser = Parser {
+            tokens,
+            at: 0,
+            ignore_case,
+            mode,
+        };
+        let filter = parser.or()?;
+        match parser.peek() {
+            None => Ok(filter),
+            Some(token) => Err(format!("Unexpected `{}` in filter", token)),
+        }
+    }
+
+    pub fn matches(&self, tra: &Transaction) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Term(term) => term.matches(tra),
+            Filter::Not(filter) => !filter.matches(tra),
+            Filter::And(left, right) => left.matches(tra) && right.matches(tra),
+            Filter::Or(left, right) => left.matches(tra) || right.matches(tra),
+        }
+    }
+
+    /// whether results should be ranked by `score` rather than kept in order
+    pub fn is_fuzzy(&self) -> bool {
+        match self {
+            Filter::All => false,
+            Filter::Term(term) => matches!(term.pattern, Some(Pattern::Fuzzy(_))),
+            Filter::Not(filter) => filter.is_fuzzy(),
+            Filter::And(left, right) | Filter::Or(left, right) => {
+                left.is_fuzzy() || right.is_fuzzy()
+            }
+        }
+    }
+
+    /// how well a matching Transaction matches, higher is better
+    pub fn score(&self, tra: &Transaction) -> i64 {
+        match self {
+            Filter::All | Filter::Not(_) => 0,
+            Filter::Term(term) => term
+                .found(tra)
+                .iter()
+                .map(|(_, found)| found.score)
+                .max()
+                .unwrap_or(0),
+            Filter::And(left, right) => left.score(tra) + right.score(tra),
+            Filter::Or(left, right) => left.score(tra).max(right.score(tra)),
+        }
+    }
+
+    /// the byte ranges of each field that matched, for highlighting
+    ///     - terms under `not` match by not being found, so they mark nothing
+    pub fn marks(&self, tra: &Transaction) -> Vec<(&'static str, Vec<(usize, usize)>)> {
+        match self {
+            Filter::All | Filter::Not(_) => Vec::new(),
+            Filter::Term(term) if term.op == Op::Contains => term
+                .found(tra)
+                .into_iter()
+                .map(|(field, found)| (field, found.ranges))
+                .collect(),
+            Filter::Term(_) => Vec::new(),
+            Filter::And(left, right) | Filter::Or(left, right) => {
+                let mut marks = left.marks(tra);
+                marks.extend(right.marks(tra));
+                marks
+            }
+        }
+    }
+}
+
+impl Term {
+    fn parse(word: &str, ignore_case: bool, mode: Mode) -> Result<Term, String> {
+        let at = match word.find(['<', '>', '=', '!', '~']) {
+            Some(at) if at > 0 => at,
+            _ => {
+                return Ok(Term {
+                    field: None,
+                    op: Op::Contains,
+                    value: SortValue::Text(word.to_string()),
+                    ignore_case,
+                    pattern: Some(Pattern::new(word, ignore_case, mode)?),
+                })
+            }
+        };
+        let (name, rest) = word.split_at(at);
+        let field = match FIELDS
+            .iter()
+            .find(|(short, _)| *short == name.to_lowercase())
+        {
+            Some((_, field)) => *field,
+            None => return Err(format!("Can't filter by {}", name)),
+        };
+        let (op, value) = match OPS.iter().find(|(op, _)| rest.starts_with(op)) {
+            Some((text, op)) => (*op, &rest[text.len()..]),
+            None => return Err(format!("Expected an operator after {}", name)),
+        };
+        let pattern = match op {
+            Op::Contains | Op::Excludes => Some(Pattern::new(value, ignore_case, mode)?),
+            _ => None,
+        };
+        let value = match field {
+            "amount" => SortValue::Num(
+                value
+                    .parse::<f64>()
+                    .map_err(|_| format!("Not an amount: {}", value))?,
+            ),
+            "date" => SortValue::Num(
+                cli::parse_date(value)
+                    .filter(|_| !value.is_empty())
+                    .ok_or(format!("Not a date: {}", value))?
+                    .and_time(NaiveTime::MIN)
+                    .and_utc()
+                    .timestamp() as f64,
+            ),
+            _ => SortValue::Text(value.to_string()),
+        };
+        if pattern.is_some() && matches!(value, SortValue::Num(_)) {
+            return Err(format!(
+                "{} can't be matched with ~, compare it instead",
+                field
+            ));
+        }
+        Ok(Term {
+            field: Some(field),
+            op,
+            value,
+            ignore_case,
+            pattern,
+        })
+    }
+
+    fn matches(&self, tra: &Transaction) -> bool {
+        match (&self.pattern, self.op) {
+            (Some(_), Op::Excludes) => self.found(tra).is_empty(),
+            (Some(_), _) => !self.found(tra).is_empty(),
+            (None, _) => self.compare(&tra.sort_value(self.field.unwrap_or_default())),
+        }
+    }
+
+    /// the fields the pattern of the term was found in
+    fn found(&self, tra: &Transaction) -> Vec<(&'static str, Found)> {
+        let pattern = match &self.pattern {
+            Some(pattern) => pattern,
+            None => return Vec::new(),
+        };
+        let fields = match self.field {
+            Some(field) => vec![field],
+            None => BARE_FIELDS.to_vec(),
+        };
+        fields
+            .into_iter()
+            .filter_map(|field| {
+                let text = match field {
+                    "date" => tra.get_date(),
+                    _ => match tra.sort_value(field) {
+                        SortValue::Text(text) => text,
+                        SortValue::Num(_) => return None,
+                    },
+                };
+                pattern.find(&text).map(|found| (field, found))
+            })
+            .collect()
+    }
+
+    /// whether `actual <op> value` holds
+    fn compare(&self, actual: &SortValue) -> bool {
+        match (actual, &self.value) {
+            (SortValue::Num(actual), SortValue::Num(value)) => match self.op {
+                Op::Eq => (actual - value).abs() < 0.005,
+                Op::Ne => (actual - value).abs() >= 0.005,
+                Op::Lt => actual < value,
+                Op::Le => actual <= value,
+                Op::Gt => actual > value,
+                Op::Ge => actual >= value,
+                Op::Contains | Op::Excludes => false,
+            },
+            (SortValue::Text(actual), SortValue::Text(value)) => {
+                let (actual, value) = if self.ignore_case {
+                    (actual.to_lowercase(), value.to_lowercase())
+                } else {
+                    (actual.clone(), value.clone())
+                };
+                match self.op {
+                    Op::Eq => actual == value,
+                    Op::Ne => actual != value,
+                    Op::Lt => actual < value,
+                    Op::Le => actual <= value,
+                    Op::Gt => actual > value,
+                    Op::Ge => actual >= value,
+                    Op::Contains | Op::Excludes => false,
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Pattern {
+    fn new(value: &str, ignore_case: bool, mode: Mode) -> Result<Pattern, String> {
+        let source = match mode {
+            Mode::Fuzzy => return Ok(Pattern::Fuzzy(value.to_lowercase().chars().collect())),
+            Mode::Regex => value.to_string(),
+            Mode::Plain => regex::escape(value),
+        };
+        RegexBuilder::new(&source)
+            .case_insensitive(ignore_case)
+            .build()
+            .map(Pattern::Regex)
+            .map_err(|e| {
+                // the last line of a regex error says what is wrong, the rest draws the pattern
+                let reason = e.to_string();
+                let reason = reason.lines().last().unwrap_or_default().to_string();
+                format!(
+                    "Not a regex: {}: {}",
+                    value,
+                    reason.trim_start_matches("error: ")
+                )
+            })
+    }
+
+    fn find(&self, text: &str) -> Option<Found> {
+        match self {
+            Pattern::Regex(regex) => {
+                let ranges: Vec<(usize, usize)> = regex
+                    .find_iter(text)
+                    .filter(|found| !found.as_str().is_empty())
+                    .map(|found| (found.start(), found.end()))
+                    .collect();
+                if ranges.is_empty() && !regex.is_match(text) {
+                    None
+                } else {
+                    Some(Found { score: 0, ranges })
+                }
+            }
+            Pattern::Fuzzy(wanted) => fuzzy(wanted, text),
+        }
+    }
+}
+
+/// find the (lowercase) wanted characters in order in text, scoring how close they are
+///     - each start of the first character is tried and the best scoring match is kept
+///     - runs of adjacent characters and matches at the start of words score higher,
+///       characters skipped in between score lower
+fn fuzzy(wanted: &[char], text: &str) -> Option<Found> {
+    let chars: Vec<(usize, char)> = text
+        .char_indices()
+        .map(|(at, c)| (at, c.to_lowercase().next().unwrap_or(c)))
+        .collect();
+    let first = *wanted.first()?;
+    let mut best: Option<(i64, Vec<usize>)> = None;
+    for start in (0..chars.len()).filter(|&i| chars[i].1 == first) {
+        let mut picked = vec![start];
+        let mut next = start + 1;
+        for &c in &wanted[1..] {
+            match (next..chars.len()).find(|&i| chars[i].1 == c) {
+                Some(i) => {
+                    picked.push(i);
+                    next = i + 1;
+                }
+                None => break,
+            }
+        }
+        if picked.len() < wanted.len() {
+            break;
+        }
+        let mut score = 0;
+        for (n, &i) in picked.iter().enumerate() {
+            score += 1;
+            if i == 0 || !chars[i - 1].1.is_alphanumeric() {
+                score += 3;
+            }
+            if n > 0 {
+                let gap = (i - picked[n - 1] - 1) as i64;
+                score += if gap == 0 { 5 } else { -gap.min(5) };
+            }
+        }
+        if best.as_ref().is_none_or(|(most, _)| score > *most) {
+            best = Some((score, picked));
+        }
+    }
+    let (score, picked) = best?;
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for i in picked {
+        let (start, c) = (chars[i].0, text[chars[i].0..].chars().next()?);
+        let end = start + c.len_utf8();
+        match ranges.last_mut() {
+            Some(last) if last.1 == start => last.1 = end,
+            _ => ranges.push((start, end)),
+        }
+    }
+    Some(Found { score, ranges })
+}
+
+/// the letters of a `-irf` style option word, None for anything else
+fn option_letters(token: &str) -> Option<&str> {
+    let letters = token.strip_prefix('-')?;
+    if !letters.is_empty() && letters.chars().all(|c| matches!(c, 'i' | 'r' | 'f')) {
+        Some(letters)
+    } else {
+        None
+    }
+}
+
+/// recursive descent over the tokens: `or` binds loosest, then `and`, then `not`
+struct Parser {
+    tokens: Vec<String>,
+    at: usize,
+    ignore_case: bool,
+    mode: Mode,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.at).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.at).cloned();
+        self.at += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Filter, String> {
+        let mut filter = self.and()?;
+        while self.peek().map(str::to_lowercase).as_deref() == Some("or") {
+            self.at += 1;
+            filter = Filter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<Filter, String> {
+        let mut filter = self.not()?;
+        loop {
+            match self.peek().map(str::to_lowercase).as_deref() {
+                None | Some("or") | Some(")") => return Ok(filter),
+                Some("and") => self.at += 1,
+                Some(_) => (),
+            }
+            filter = Filter::And(Box::new(filter), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<Filter, String> {
+        let token = match self.next() {
+            Some(token) => token,
+            None => return Err(String::from("Filter ended early")),
+        };
+        match token.to_lowercase().as_str() {
+            "not" => Ok(Filter::Not(Box::new(self.not()?))),
+            "(" => {
+                let filter = self.or()?;
+                match self.next().as_deref() {
+                    Some(")") => Ok(filter),
+                    _ => Err(String::from("Missing ) in filter")),
+                }
+            }
+            "and" | "or" | ")" => Err(format!("Unexpected `{}` in filter", token)),
+            _ => Term::parse(&token, self.ignore_case, self.mode).map(Filter::Term),
+        }
+    }
+}
+
+/// the words of a query, with parentheses as words of their own
+///     - a closing parenthesis is only split off when the word did not open it, so a
+///       regex like `desc~(cafe|coffee)` stays whole
+fn tokenize(query: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    for word in cli::split_words(query) {
+        let mut word = word.as_str();
+        while let Some(rest) = word.strip_prefix('(') {
+            tokens.push(String::from("("));
+            word = rest;
+        }
+        let mut closing = 0;
+        while word.ends_with(')') && word.matches(')').count() > word.matches('(').count() {
+            closing += 1;
+            word = &word[..word.len() - 1];
+        }
+        if !word.is_empty() {
+            tokens.push(word.to_string());
+        }
+        tokens.extend(std::iter::repeat_n(String::from(")"), closing));
+    }
+    tokens
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn tra(day: u32, amount: f32, account: &str, category: &str, description: &str) -> Transaction {
+        Transaction::from_parts(
+            Utc.with_ymd_and_hms(2026, 10, day, 0, 0, 0).unwrap(),
+            amount,
+            account.to_string(),
+            category.to_string(),
+            description.to_string(),
+        )
+    }
+
+    fn sample() -> Vec<Transaction> {
+        vec![
+            tra(2, -12.5, "Checking", "Food", "SAFEWAY #1234"),
+            tra(3, -4.0, "Cash", "Dining", "Corner Cafe"),
+            tra(5, 1000.0, "Checking", "<empty>", "PAYROLL & CO"),
+            tra(18, -13.0, "Checking", "Dining", "Lunch, office"),
+        ]
+    }
+
+    /// the descriptions of the sample Transactions query matches
+    fn found(query: &str) -> Vec<String> {
+        let filter = Filter::parse(query).unwrap();
+        sample()
+            .iter()
+            .filter(|tra| filter.matches(tra))
+            .map(|tra| tra.get_description().to_string())
+            .collect()
+    }
+
+    fn error(query: &str) -> String {
+        Filter::parse(query).err().unwrap_or_default()
+    }
+
+    #[test]
+    fn empty_matches_everything() {
+        assert_eq!(found("").len(), 4);
+    }
+
+    #[test]
+    fn terms_next_to_each_other_all_match() {
+        assert_eq!(found("cat=Dining acc=Checking"), ["Lunch, office"]);
+        assert_eq!(found("cat=Dining and amount>-10"), ["Corner Cafe"]);
+    }
+
+    #[test]
+    fn or_binds_looser_than_and() {
+        // cat=Food or (cat=Dining acc=Cash)
+        assert_eq!(
+            found("cat=Food or cat=Dining acc=Cash"),
+            ["SAFEWAY #1234", "Corner Cafe"]
+        );
+        assert_eq!(
+            found("(cat=Food or cat=Dining) acc=Checking"),
+            ["SAFEWAY #1234", "Lunch, office"]
+        );
+    }
+
+    #[test]
+    fn not_applies_to_the_next_term_or_group() {
+        assert_eq!(found("not acc=Checking"), ["Corner Cafe"]);
+        assert_eq!(
+            found("not (acc=Cash or amount>0)"),
+            ["SAFEWAY #1234", "Lunch, office"]
+        );
+        assert_eq!(found("not acc=Cash cat=Dining"), ["Lunch, office"]);
+        assert_eq!(found("not not acc=Cash"), ["Corner Cafe"]);
+    }
+
+    #[test]
+    fn bare_words_and_contains() {
+        assert_eq!(found("Cafe"), ["Corner Cafe"]);
+        assert_eq!(found("desc~office"), ["Lunch, office"]);
+        assert_eq!(
+            found("desc!~a"),
+            ["SAFEWAY #1234", "PAYROLL & CO", "Lunch, office"]
+        );
+        assert!(found("cafe").is_empty());
+        assert_eq!(found("-i cafe"), ["Corner Cafe"]);
+        assert_eq!(found(r#"desc~"Corner Cafe""#), ["Corner Cafe"]);
+    }
+
+    #[test]
+    fn parenthesised_regexes_stay_whole() {
+        assert_eq!(
+            tokenize("(desc~(Cafe|office) or cat=Food)"),
+            ["(", "desc~(Cafe|office)", "or", "cat=Food", ")"]
+        );
+        assert_eq!(
+            found("-r (desc~(Cafe|office))"),
+            ["Corner Cafe", "Lunch, office"]
+        );
+        assert_eq!(
+            found("-ri desc~^(safe|payroll)"),
+            ["SAFEWAY #1234", "PAYROLL & CO"]
+        );
+        // without -r the parentheses are plain text
+        assert!(found("desc~(Cafe|office)").is_empty());
+    }
+
+    #[test]
+    fn date_terms() {
+        assert_eq!(
+            found("date>=10/03/2026 date<2026-10-18"),
+            ["Corner Cafe", "PAYROLL & CO"]
+        );
+        assert_eq!(found("date=10/18/2026"), ["Lunch, office"]);
+        assert_eq!(found("10/02/2026"), ["SAFEWAY #1234"]);
+        assert_eq!(error("date>soon"), "Not a date: soon");
+        assert_eq!(error("date="), "Not a date: ");
+    }
+
+    #[test]
+    fn bad_queries() {
+        assert_eq!(error("colour=red"), "Can't filter by colour");
+        assert_eq!(error("cat!Food"), "Expected an operator after cat");
+        assert_eq!(error("amount=ten"), "Not an amount: ten");
+        assert_eq!(
+            error("amount~5"),
+            "amount can't be matched with ~, compare it instead"
+        );
+        assert_eq!(error("cat=Food or"), "Filter ended early");
+        assert_eq!(error("(cat=Food"), "Missing ) in filter");
+        assert_eq!(error("cat=Food )"), "Unexpected `)` in filter");
+        assert_eq!(error("or cat=Food"), "Unexpected `or` in filter");
+        assert_eq!(error("-rf cafe"), "Use either -r or -f, not both");
+        assert!(error("-r desc~(cafe").starts_with("Not a regex: (cafe"));
+    }
+
+    #[test]
+    fn fuzzy_finds_letters_in_order() {
+        let wanted: Vec<char> = "ntflx".chars().collect();
+        let found = fuzzy(&wanted, "NETFLIX.COM").unwrap();
+        assert_eq!(found.ranges, [(0, 1), (2, 5), (6, 7)]);
+        assert!(fuzzy(&wanted, "FLIX NET").is_none());
+        assert!(fuzzy(&wanted, "").is_none());
+    }
+
+    #[test]
+    fn fuzzy_prefers_runs_and_word_starts() {
+        let wanted: Vec<char> = "caf".chars().collect();
+        let run = fuzzy(&wanted, "Corner Cafe").unwrap();
+        let spread = fuzzy(&wanted, "Cash at Farm").unwrap();
+        assert!(run.score > spread.score);
+        // the best start is kept, not the first
+        assert_eq!(run.ranges, [(7, 10)]);
+    }
+
+    #[test]
+    fn fuzzy_filters_rank_matches() {
+        let filter = Filter::parse("-f desc~cfe").unwrap();
+        assert!(filter.is_fuzzy());
+        let transactions = sample();
+        let cafe = &transactions[1];
+        assert!(filter.matches(cafe));
+        assert!(!filter.matches(&transactions[0]));
+        assert!(filter.score(cafe) > 0);
+        assert_eq!(filter.marks(cafe), [("description", vec![(7, 8), (9, 11)])]);
+        assert!(!Filter::parse("desc~cfe").unwrap().is_fuzzy());
+    }
+}
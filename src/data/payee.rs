@@ -0,0 +1,248 @@
+use crate::cli::{self, Content};
+use std::fmt;
+use uuid::{adapter::Simple, Uuid};
+
+/// a merchant or person Transactions are paid to or received from
+///     - the id is saved so Transactions in every month can link to it
+///     - aliases are other spellings found in statements, e.g. "AMZN MKTP" for Amazon
+///     - category and account pre-fill the prompts of new Transactions
+#[derive(Clone, Debug)]
+pub struct Payee {
+    id: Uuid,
+    name: String,
+    aliases: Vec<String>,
+    category: String,
+    account: String,
+}
+
+impl Payee {
+    /// the prompts answered by the words after `a pay`, in order
+    pub const FIELDS: [&'static str; 4] =
+        ["name", "aliases", "default-category", "default-account"];
+
+    /// column headers matching `to_row`
+    pub const HEADERS: [&'static str; 5] = ["id", "name", "aliases", "category", "account"];
+
+    pub fn get_id(&self) -> String {
+        self.id.to_string()
+    }
+
+    pub fn get_simple_id(&self) -> String {
+        self.simplify_id()
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_category(&self) -> &str {
+        &self.category
+    }
+
+    pub fn set_category(&mut self, new_category: String) {
+        self.category = new_category;
+    }
+
+    pub fn get_account(&self) -> &str {
+        &self.account
+    }
+
+    pub fn set_account(&mut self, new_account: String) {
+        self.account = new_account;
+    }
+
+    pub fn from_cls(
+        possible_id: Option<&str>,
+        possible_name: Option<&str>,
+        possible_aliases: Option<&str>,
+        possible_category: Option<&str>,
+        possible_account: Option<&str>,
+    ) -> Payee {
+        Payee {
+            id: cli::id_from_cls(possible_id),
+            name: possible_name.unwrap_or("").to_string(),
+            aliases: split_aliases(possible_aliases.unwrap_or("")),
+            category: possible_category.unwrap_or("").to_string(),
+            account: possible_account.unwrap_or("").to_string(),
+        }
+    }
+
+    /// build a Payee from a Transaction being entered, remembering its category and account
+    pub fn from_parts(name: String, category: String, account: String) -> Payee {
+        Payee {
+            id: Uuid::new_v4(),
+            name,
+            aliases: Vec::new(),
+            category,
+            account,
+        }
+    }
+
+    pub fn new() -> Payee {
+        let name = cli::get_input("Name");
+        let aliases = cli::get_input("Aliases (separated by ;)");
+        let category = cli::get_input("Default Category");
+        let account = cli::get_input("Default Account");
+        Payee {
+            id: Uuid::new_v4(),
+            name,
+            aliases: split_aliases(&aliases),
+            category,
+            account,
+        }
+    }
+
+    pub fn find(payees: &[Payee]) -> i32 {
+        let arg = cli::get_input("ID");
+        for (index, payee) in payees.iter().enumerate() {
+            if payee.simplify_id().contains(&arg) {
+                return index as i32;
+            }
+        }
+        cli::fail(&format!("No Payee with ID {}", arg));
+        -1
+    }
+
+    /// the Payee text refers to
+    ///     - an exact name match wins, then a name or alias found as whole words in text, so
+    ///       "Shell" is not found in "Seashell Gifts"
+    pub fn resolve(payees: &[Payee], text: &str) -> Option<usize> {
+        let text = text.trim().to_lowercase();
+        if text.is_empty() {
+            return None;
+        }
+        let words = words(&text);
+        payees
+            .iter()
+            .position(|payee| payee.name.to_lowercase() == text)
+            .or_else(|| payees.iter().position(|payee| payee.matches(&words)))
+    }
+
+    pub fn edit(&mut self, field: &str) {
+        if field == "name" {
+            self.name = cli::get_input("Name");
+        } else if field == "aliases" {
+            self.aliases = split_aliases(&cli::get_input("Aliases (separated by ;)"));
+        } else if field == "category" {
+            self.category = cli::get_input("Default Category");
+        } else if field == "account" {
+            self.account = cli::get_input("Default Account");
+        } else {
+            cli::fail(&format!("Unknown field: {}", field));
+        }
+    }
+
+    /// whether the words of a text contain the words of the name or one of the aliases, in order
+    fn matches(&self, text: &[String]) -> bool {
+        std::iter::once(&self.name)
+            .chain(self.aliases.iter())
+            .any(|name| {
+                let name = words(name);
+                !name.is_empty() && text.windows(name.len()).any(|window| window == name)
+            })
+    }
+
+    /// the cells of a table row, in the order of `HEADERS`
+    pub fn to_row(&self) -> Vec<Content> {
+        vec![
+            Content::St(self.simplify_id()),
+            Content::St(self.name.clone()),
+            Content::St(self.aliases.join("; ")),
+            Content::St(self.category.clone()),
+            Content::St(self.account.clone()),
+        ]
+    }
+
+    fn simplify_id(&self) -> String {
+        let id = Simple::from_uuid(self.id);
+        let mut id = id.to_string();
+        let (id_string, _extra) = id.split_at_mut(6);
+        id_string.to_string()
+    }
+
+    pub fn to_cls(&self) -> String {
+        let mut st = String::new();
+        st.push_str(&self.id.to_string());
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.name));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.aliases.join(";")));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.category));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.account));
+        st.push('\n');
+        st
+    }
+}
+
+impl fmt::Display for Payee {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},\t{}\t\t{}\t\t{}\t\t{}",
+            self.simplify_id(),
+            self.name,
+            self.aliases.join("; "),
+            self.category,
+            self.account
+        )
+    }
+}
+
+/// the lowercase words of text, split at anything but letters and digits
+fn words(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn split_aliases(aliases: &str) -> Vec<String> {
+    aliases
+        .split(';')
+        .map(str::trim)
+        .filter(|alias| !alias.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn payees() -> Vec<Payee> {
+        vec![
+            Payee::from_cls(
+                None,
+                Some("Amazon"),
+                Some("AMZN MKTP; amzn.com"),
+                None,
+                None,
+            ),
+            Payee::from_cls(None, Some("Shell"), None, None, None),
+            Payee::from_cls(None, Some("Shell Recharge"), None, None, None),
+        ]
+    }
+
+    #[test]
+    fn resolve_exact_names_first() {
+        let payees = payees();
+        assert_eq!(Payee::resolve(&payees, "shell recharge"), Some(2));
+        assert_eq!(Payee::resolve(&payees, " Amazon "), Some(0));
+        assert_eq!(Payee::resolve(&payees, ""), None);
+    }
+
+    #[test]
+    fn resolve_whole_words() {
+        let payees = payees();
+        assert_eq!(Payee::resolve(&payees, "SHELL OIL 0042"), Some(1));
+        assert_eq!(Payee::resolve(&payees, "Amazon.com order"), Some(0));
+        assert_eq!(Payee::resolve(&payees, "AMZN Mktp US*2K4"), Some(0));
+        assert_eq!(Payee::resolve(&payees, "AMZN.COM/BILL"), Some(0));
+        assert_eq!(Payee::resolve(&payees, "Seashell Gifts"), None);
+        assert_eq!(Payee::resolve(&payees, "Amazonia Cafe"), None);
+        assert_eq!(Payee::resolve(&payees, "AMZN fresh"), None);
+    }
+}
@@ -0,0 +1,362 @@
+use crate::cli::{self, Content};
+use crate::data::transaction::Transaction;
+use regex::{Regex, RegexBuilder};
+use std::fmt;
+use uuid::{adapter::Simple, Uuid};
+
+/// an auto-categorization rule
+///     - pattern is a substring of the description, or a regex written `/like this/`, both
+///       ignoring case
+///     - min, max and match_account narrow which Transactions the rule applies to
+///     - category, account and tags are what a matching Transaction gets
+#[derive(Clone, Debug)]
+pub struct Rule {
+    id: Uuid,
+    pattern: String,
+    regex: Option<Regex>,
+    min: Option<f32>,
+    max: Option<f32>,
+    match_account: String,
+    category: String,
+    account: String,
+    tags: Vec<String>,
+}
+
+impl Rule {
+    /// the prompts answered by the words after `a rul`, in order
+    pub const FIELDS: [&'static str; 7] = [
+        "pattern",
+        "category",
+        "minimum-amount",
+        "maximum-amount",
+        "only-on-account",
+        "move-to-account",
+        "tags",
+    ];
+
+    /// column headers matching `to_row`
+    pub const HEADERS: [&'static str; 7] = [
+        "id",
+        "pattern",
+        "amount",
+        "on account",
+        "category",
+        "account",
+        "tags",
+    ];
+
+    pub fn get_simple_id(&self) -> String {
+        self.simplify_id()
+    }
+
+    pub fn get_pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    pub fn get_category(&self) -> &str {
+        &self.category
+    }
+
+    pub fn set_category(&mut self, new_category: String) {
+        self.category = new_category;
+    }
+
+    pub fn get_account(&self) -> &str {
+        &self.account
+    }
+
+    pub fn set_account(&mut self, new_account: String) {
+        self.account = new_account;
+    }
+
+    pub fn get_match_account(&self) -> &str {
+        &self.match_account
+    }
+
+    pub fn set_match_account(&mut self, new_match_account: String) {
+        self.match_account = new_match_account;
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn from_cls(
+        possible_pattern: Option<&str>,
+        possible_min: Option<&str>,
+        possible_max: Option<&str>,
+        possible_match_account: Option<&str>,
+        possible_category: Option<&str>,
+        possible_account: Option<&str>,
+        possible_tags: Option<&str>,
+        possible_id: Option<&str>,
+    ) -> Rule {
+        let pattern = possible_pattern.unwrap_or("").to_string();
+        Rule {
+            id: cli::id_from_cls(possible_id),
+            regex: compile(&pattern),
+            pattern,
+            min: possible_min.and_then(|min| min.parse().ok()),
+            max: possible_max.and_then(|max| max.parse().ok()),
+            match_account: possible_match_account.unwrap_or("").to_string(),
+            category: possible_category.unwrap_or("").to_string(),
+            account: possible_account.unwrap_or("").to_string(),
+            tags: possible_tags
+                .unwrap_or("")
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+        }
+    }
+
+    /// ask for a new Rule, None if no pattern is given
+    pub fn new() -> Option<Rule> {
+        let pattern = cli::get_input("Pattern (substring, or /regex/)");
+        if pattern.is_empty() {
+            cli::fail("A Rule needs a pattern, it would match every Transaction");
+            return None;
+        }
+        let min = optional_money(cli::get_input("Minimum amount (blank for none)"));
+        let max = optional_money(cli::get_input("Maximum amount (blank for none)"));
+        let match_account = cli::get_input("Only on Account (blank for any)");
+        let category = cli::get_input("Category");
+        let account = cli::get_input("Move to Account (blank to keep)");
+        let tags = cli::get_input("Tags (space separated)");
+        Some(Rule {
+            id: Uuid::new_v4(),
+            regex: compile(&pattern),
+            pattern,
+            min,
+            max,
+            match_account,
+            category,
+            account,
+            tags: tags.split_whitespace().map(String::from).collect(),
+        })
+    }
+
+    pub fn find(rules: &[Rule]) -> i32 {
+        let arg = cli::get_input("ID");
+        for (index, rule) in rules.iter().enumerate() {
+            if rule.simplify_id().contains(&arg) {
+                return index as i32;
+            }
+        }
+        cli::fail(&format!("No Rule with ID {}", arg));
+        -1
+    }
+
+    pub fn edit(&mut self, field: &str) {
+        if field == "pattern" {
+            let pattern = cli::get_input("Pattern (substring, or /regex/)");
+            if pattern.is_empty() {
+                cli::fail("A Rule needs a pattern, it would match every Transaction");
+                return;
+            }
+            self.pattern = pattern;
+            self.regex = compile(&self.pattern);
+        } else if field == "min" {
+            self.min = optional_money(cli::get_input("Minimum amount (blank for none)"));
+        } else if field == "max" {
+            self.max = optional_money(cli::get_input("Maximum amount (blank for none)"));
+        } else if field == "on account" {
+            self.match_account = cli::get_input("Only on Account (blank for any)");
+        } else if field == "category" {
+            self.category = cli::get_input("Category");
+        } else if field == "account" {
+            self.account = cli::get_input("Move to Account (blank to keep)");
+        } else if field == "tags" {
+            self.tags = cli::get_input("Tags (space separated)")
+                .split_whitespace()
+                .map(String::from)
+                .collect();
+        } else {
+            cli::fail(&format!("Unknown field: {}", field));
+        }
+    }
+
+    /// whether the rule applies to tra
+    pub fn matches(&self, tra: &Transaction) -> bool {
+        let description_matches = match &self.regex {
+            Some(regex) => regex.is_match(tra.get_description()),
+            None if is_regex(&self.pattern) => false,
+            None => tra
+                .get_description()
+                .to_lowercase()
+                .contains(&self.pattern.to_lowercase()),
+        };
+        description_matches
+            && self.min.is_none_or(|min| tra.get_amount() >= min)
+            && self.max.is_none_or(|max| tra.get_amount() <= max)
+            && (self.match_account.is_empty() || self.match_account == tra.get_account())
+    }
+
+    /// give tra the rule's category, account and tags
+    pub fn apply(&self, tra: &mut Transaction) {
+        if !self.category.is_empty() {
+            tra.set_category(self.category.clone());
+        }
+        if !self.account.is_empty() {
+            tra.set_account(self.account.clone());
+        }
+        tra.add_tags(&self.tags);
+    }
+
+    /// the cells of a table row, in the order of `HEADERS`
+    pub fn to_row(&self) -> Vec<Content> {
+        vec![
+            Content::St(self.simplify_id()),
+            Content::St(self.pattern.clone()),
+            Content::St(self.amount_range()),
+            Content::St(self.match_account.clone()),
+            Content::St(self.category.clone()),
+            Content::St(self.account.clone()),
+            Content::St(self.tags.join(" ")),
+        ]
+    }
+
+    fn amount_range(&self) -> String {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => format!("{} to {}", min, max),
+            (Some(min), None) => format!(">= {}", min),
+            (None, Some(max)) => format!("<= {}", max),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn simplify_id(&self) -> String {
+        let id = Simple::from_uuid(self.id);
+        let mut id = id.to_string();
+        let (id_string, _extra) = id.split_at_mut(6);
+        id_string.to_string()
+    }
+
+    pub fn to_cls(&self) -> String {
+        let mut st = String::new();
+        st.push_str(&cli::escape_cls(&self.pattern));
+        st.push(',');
+        st.push_str(&self.min.map_or(String::new(), |min| min.to_string()));
+        st.push(',');
+        st.push_str(&self.max.map_or(String::new(), |max| max.to_string()));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.match_account));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.category));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.account));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.tags.join(" ")));
+        st.push(',');
+        st.push_str(&self.id.to_string());
+        st.push('\n');
+        st
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{},\t{}\t\t{}\t\t{}\t\t{}\t\t{}",
+            self.simplify_id(),
+            self.pattern,
+            self.amount_range(),
+            self.category,
+            self.account,
+            self.tags.join(" ")
+        )
+    }
+}
+
+fn is_regex(pattern: &str) -> bool {
+    pattern.len() > 1 && pattern.starts_with('/') && pattern.ends_with('/')
+}
+
+fn compile(pattern: &str) -> Option<Regex> {
+    if !is_regex(pattern) {
+        return None;
+    }
+    match RegexBuilder::new(&pattern[1..pattern.len() - 1])
+        .case_insensitive(true)
+        .build()
+    {
+        Ok(regex) => Some(regex),
+        Err(e) => {
+            eprintln!("Error in rule pattern {}: {}", pattern, e);
+            None
+        }
+    }
+}
+
+fn optional_money(possible_num: String) -> Option<f32> {
+    if possible_num.is_empty() {
+        None
+    } else {
+        Some(cli::try_into_money(&possible_num))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn rule(pattern: &str, min: &str, max: &str, match_account: &str) -> Rule {
+        Rule::from_cls(
+            Some(pattern),
+            Some(min),
+            Some(max),
+            Some(match_account),
+            Some("Dining"),
+            Some("Card"),
+            Some("lunch work"),
+            None,
+        )
+    }
+
+    fn tra(amount: f32, account: &str, description: &str) -> Transaction {
+        Transaction::from_parts(
+            Utc.with_ymd_and_hms(2026, 10, 1, 0, 0, 0).unwrap(),
+            amount,
+            account.to_string(),
+            String::from("<empty>"),
+            description.to_string(),
+        )
+    }
+
+    #[test]
+    fn substrings_ignore_case() {
+        let cafe = rule("corner cafe", "", "", "");
+        assert!(cafe.matches(&tra(-5.0, "Checking", "CORNER CAFE #42")));
+        assert!(!cafe.matches(&tra(-5.0, "Checking", "Corner Bakery")));
+    }
+
+    #[test]
+    fn regexes_ignore_case() {
+        let fuel = rule("/^(shell|bp) /", "", "", "");
+        assert!(fuel.matches(&tra(-40.0, "Checking", "SHELL 0042")));
+        assert!(fuel.matches(&tra(-40.0, "Checking", "bp station")));
+        assert!(!fuel.matches(&tra(-40.0, "Checking", "Seashell shop")));
+        // a pattern that doesn't compile matches nothing, rather than its text
+        let broken = rule("/(shell/", "", "", "");
+        assert!(!broken.matches(&tra(-40.0, "Checking", "/(shell/")));
+    }
+
+    #[test]
+    fn amount_range_and_account() {
+        let small = rule("cafe", "-20", "0", "Checking");
+        assert!(small.matches(&tra(-12.5, "Checking", "Cafe")));
+        assert!(small.matches(&tra(-20.0, "Checking", "Cafe")));
+        assert!(!small.matches(&tra(-25.0, "Checking", "Cafe")));
+        assert!(!small.matches(&tra(5.0, "Checking", "Cafe")));
+        assert!(!small.matches(&tra(-12.5, "Savings", "Cafe")));
+    }
+
+    #[test]
+    fn apply_sets_category_account_and_tags() {
+        let cafe = rule("cafe", "", "", "");
+        let mut lunch = tra(-12.5, "Checking", "Cafe");
+        lunch.add_tags(&[String::from("work")]);
+        cafe.apply(&mut lunch);
+        assert_eq!(lunch.get_category(), "Dining");
+        assert_eq!(lunch.get_account(), "Card");
+        assert!(lunch.to_cls().contains(",work lunch,"));
+    }
+}
@@ -1,4 +1,10 @@
-use crate::cli::{self, Content};
+use crate::cli::{self, Content, Output, Report};
+use crate::data::account::Account;
+use crate::data::category::Category;
+use crate::data::filter::Filter;
+use crate::data::payee::Payee;
+use crate::settings;
+use crate::view::SortValue;
 use chrono::{DateTime, Utc};
 use std::fmt;
 use uuid::{adapter::Simple, Uuid};
@@ -11,9 +17,30 @@ pub struct Transaction {
     account: String,
     category: String,
     description: String,
+    import_id: String,
+    tags: Vec<String>,
+    payee: String,
 }
 
 impl Transaction {
+    /// the prompts answered by the words after `a tra`, in order
+    pub const FIELDS: [&'static str; 5] = ["date", "amount", "account", "category", "description"];
+
+    /// column headers matching `to_row`
+    pub const HEADERS: [&'static str; 7] = [
+        "id",
+        "date",
+        "amount",
+        "account",
+        "category",
+        "description",
+        "tags",
+    ];
+
+    /// the fields `s tra` can sort by
+    pub const SORT_FIELDS: [&'static str; 5] =
+        ["date", "amount", "account", "category", "description"];
+
     pub fn get_simple_id(&self) -> String {
         self.simplify_id()
     }
@@ -22,6 +49,10 @@ impl Transaction {
         self.date.format("%m/%d/%Y").to_string()
     }
 
+    pub fn get_date_time(&self) -> DateTime<Utc> {
+        self.date
+    }
+
     pub fn get_amount(&self) -> f32 {
         self.amount
     }
@@ -46,15 +77,50 @@ impl Transaction {
         &self.description
     }
 
+    pub fn set_description(&mut self, new_description: String) {
+        self.description = new_description;
+    }
+
+    pub fn get_import_id(&self) -> &str {
+        &self.import_id
+    }
+
+    pub fn set_import_id(&mut self, new_import_id: String) {
+        self.import_id = new_import_id;
+    }
+
+    /// the full id of the linked Payee, empty if there is none
+    pub fn get_payee(&self) -> &str {
+        &self.payee
+    }
+
+    pub fn set_payee(&mut self, new_payee: String) {
+        self.payee = new_payee;
+    }
+
+    /// add tags not already on the Transaction
+    pub fn add_tags(&mut self, new_tags: &[String]) {
+        for tag in new_tags {
+            if !self.tags.contains(tag) {
+                self.tags.push(tag.clone());
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
     pub fn from_cls(
         possible_date: Option<&str>,
         possible_amount: Option<&str>,
         possible_account: Option<&str>,
         possible_category: Option<&str>,
         possible_description: Option<&str>,
+        possible_import_id: Option<&str>,
+        possible_tags: Option<&str>,
+        possible_payee: Option<&str>,
+        possible_id: Option<&str>,
     ) -> Transaction {
         Transaction {
-            id: Uuid::new_v4(),
+            id: cli::id_from_cls(possible_id),
             date: match possible_date {
                 Some(datetime) => datetime
                     .parse::<DateTime<Utc>>()
@@ -62,10 +128,7 @@ impl Transaction {
                 None => panic!("No date to parse"),
             },
             amount: match possible_amount {
-                Some(amount) => match str::parse(amount) {
-                    Ok(parsed) => parsed,
-                    Err(_) => 0.0,
-                },
+                Some(amount) => str::parse(amount).unwrap_or(0.0),
                 None => 0.0,
             },
             account: match possible_account {
@@ -80,16 +143,29 @@ impl Transaction {
                 Some(description) => String::from(description),
                 None => String::new(),
             },
+            import_id: match possible_import_id {
+                Some(import_id) => String::from(import_id),
+                None => String::new(),
+            },
+            tags: match possible_tags {
+                Some(tags) => tags.split_whitespace().map(String::from).collect(),
+                None => Vec::new(),
+            },
+            payee: match possible_payee {
+                Some(payee) => String::from(payee),
+                None => String::new(),
+            },
         }
     }
 
-    pub fn new() -> Transaction {
-        let date = cli::try_into_date(&cli::get_input("Date")); // TODO: use some Date object
-        let mut possible_amount = cli::get_input("Amount");
-        let amount = cli::try_into_money(&mut possible_amount);
-        let account = cli::get_input("Account"); //TODO: compare with Account names
-        let category = cli::get_input("Category"); //TODO: compare with Category names
-        let description = cli::get_input("Description");
+    /// build a Transaction without prompting, e.g. from an imported statement
+    pub fn from_parts(
+        date: DateTime<Utc>,
+        amount: f32,
+        account: String,
+        category: String,
+        description: String,
+    ) -> Transaction {
         Transaction {
             id: Uuid::new_v4(),
             date,
@@ -97,62 +173,221 @@ impl Transaction {
             account,
             category,
             description,
+            import_id: String::new(),
+            tags: Vec::new(),
+            payee: String::new(),
         }
     }
 
-    pub fn search(transactions: &Vec<Transaction>, arg: &String) {
-        let mut searched = Vec::new();
-        for tra in transactions.iter() {
-            if tra.date.to_string().contains(arg)
-                || tra.account.contains(arg)
-                || tra.category.contains(arg)
-                || tra.description.contains(arg)
-            {
-                searched.push({
-                    let mut tmp = Vec::new();
-                    tmp.push(Content::St(tra.get_simple_id()));
-                    tmp.push(Content::St(tra.get_date()));
-                    tmp.push(Content::Num(tra.get_amount().to_string()));
-                    tmp.push(Content::St(tra.get_account().to_string()));
-                    tmp.push(Content::St(tra.get_category().to_string()));
-                    tmp.push(Content::St(tra.get_description().to_string()));
-                    tmp
-                });
+    /// prompt for a new Transaction
+    ///     - a known Payee (by name or alias) pre-fills the Account, Category and Description
+    ///     - an unknown Payee is checked against the Payee names, and if it is created it remembers
+    ///       the Account and Category entered
+    ///     - unknown Account and Category names are checked against accounts and categories
+    pub fn new(
+        payees: &mut Vec<Payee>,
+        accounts: &mut Vec<Account>,
+        categories: &mut Vec<Category>,
+    ) -> Transaction {
+        let date = cli::get_date("Date"); // TODO: use some Date object
+        let possible_amount = cli::get_input("Amount");
+        let amount = cli::try_into_money(&possible_amount);
+        let mut payee_text = cli::get_input("Payee (blank for none)");
+        let mut known = Payee::resolve(payees, &payee_text);
+        let mut create_payee = false;
+        if known.is_none() {
+            let names: Vec<&str> = payees.iter().map(|payee| payee.get_name()).collect();
+            // a name that failed has already failed the command, so it is left blank
+            let (name, create) = cli::check_name("Payee", payee_text, &names).unwrap_or_default();
+            if !name.is_empty() {
+                known = payees.iter().position(|payee| payee.get_name() == name);
             }
+            payee_text = name;
+            create_payee = create;
         }
-        println!("==== Search Results ====");
-        cli::make_table(
-            vec!["id", "date", "amount", "account", "category", "description"],
-            &searched,
-        );
+        let (account, category, description) = match known {
+            Some(index) => {
+                let payee = &payees[index];
+                note!("Payee {} ({})", payee.get_name(), payee.get_simple_id());
+                let account = cli::get_input_or("Account", payee.get_account());
+                let account = Account::check(accounts, account).unwrap_or_default();
+                let category = cli::get_input_or("Category", payee.get_category());
+                let category = Category::check(categories, category).unwrap_or_default();
+                (
+                    account,
+                    category,
+                    cli::get_input_or("Description", payee.get_name()),
+                )
+            }
+            None => {
+                let account = Account::check(accounts, cli::get_input("Account"));
+                let category = Category::check(categories, cli::get_input("Category"));
+                (
+                    account.unwrap_or_default(),
+                    category.unwrap_or_default(),
+                    cli::get_input_or("Description", &payee_text),
+                )
+            }
+        };
+        let payee = match known {
+            Some(index) => payees[index].get_id(),
+            None if create_payee => {
+                let payee = Payee::from_parts(payee_text, category.clone(), account.clone());
+                let id = payee.get_id();
+                payees.push(payee);
+                id
+            }
+            None => String::new(),
+        };
+        Transaction {
+            id: Uuid::new_v4(),
+            date,
+            amount,
+            account,
+            category,
+            description,
+            import_id: String::new(),
+            tags: Vec::new(),
+            payee,
+        }
+    }
+
+    pub fn search(transactions: &[&Transaction], arg: &str, output: Output) {
+        match Filter::parse(arg) {
+            Ok(filter) => {
+                Transaction::show(transactions, &filter, "==== Search Results ====", output)
+            }
+            Err(e) => cli::fail(&e),
+        }
+    }
+
+    /// print the Transactions matching filter under title, followed by their total
+    pub fn show(transactions: &[&Transaction], filter: &Filter, title: &str, output: Output) {
+        let mut matched: Vec<&Transaction> = transactions
+            .iter()
+            .copied()
+            .filter(|tra| filter.matches(tra))
+            .collect();
+        if filter.is_fuzzy() {
+            // best matches first, ties keep their order
+            matched.sort_by_key(|tra| std::cmp::Reverse(filter.score(tra)));
+        }
+        let mut contents = Vec::new();
+        let mut total = 0.0;
+        for tra in matched {
+            total += tra.amount;
+            let mut row = tra.to_row();
+            for (field, ranges) in filter.marks(tra) {
+                let column = match Transaction::HEADERS.iter().position(|h| *h == field) {
+                    Some(column) => column,
+                    None => continue,
+                };
+                row[column] = match std::mem::replace(&mut row[column], Content::St(String::new()))
+                {
+                    Content::Marked(text, mut marked) => {
+                        marked.extend(ranges);
+                        Content::Marked(text, marked)
+                    }
+                    other => Content::Marked(other.text().to_string(), ranges),
+                };
+            }
+            contents.push(row);
+        }
+        let count = contents.len();
+        Report {
+            kind: "tra",
+            title,
+            headers: Transaction::HEADERS.to_vec(),
+            contents,
+            totals: vec![("count", count as f32), ("total", total)],
+            summary: format!("{} transactions, total ${}", count, cli::money_round(total)),
+            summary_below: true,
+        }
+        .print(output);
+    }
+
+    /// the cells of a table row, in the order of `HEADERS`
+    pub fn to_row(&self) -> Vec<Content> {
+        vec![
+            Content::St(self.get_simple_id()),
+            Content::St(self.get_date()),
+            Content::Toned(
+                self.get_amount().to_string(),
+                settings::amount_tone(self.get_amount()),
+            ),
+            Content::St(self.get_account().to_string()),
+            Content::St(self.get_category().to_string()),
+            Content::St(self.get_description().to_string()),
+            Content::St(self.tags.join(" ")),
+        ]
     }
 
-    pub fn find(transactions: &Vec<Transaction>) -> i32 {
+    pub fn find(transactions: &[Transaction]) -> i32 {
         let arg = cli::get_input("ID");
-        let mut index = 0;
-        for tra in transactions {
+        for (index, tra) in transactions.iter().enumerate() {
             if tra.simplify_id().contains(&arg) {
-                return index;
+                return index as i32;
             }
-            index += 1;
         }
+        cli::fail(&format!("No Transaction with ID {}", arg));
         -1
     }
 
-    pub fn edit(&mut self) {
-        let field = cli::get_input("Field to edit");
+    /// ask for a new value of field, checking account and category names like `new`
+    pub fn edit(
+        &mut self,
+        field: &str,
+        accounts: &mut Vec<Account>,
+        categories: &mut Vec<Category>,
+    ) {
         if field == "date" {
-            self.date = cli::try_into_date(&cli::get_input("Date"));
+            self.date = cli::get_date("Date");
         } else if field == "amount" {
-            self.amount = cli::try_into_money(&mut cli::get_input("Amount"));
+            self.amount = cli::try_into_money(&cli::get_input("Amount"));
         } else if field == "account" {
-            self.account = cli::get_input("Account");
+            if let Some(account) = Account::check(accounts, cli::get_input("Account")) {
+                self.account = account;
+            }
         } else if field == "category" {
-            self.category = cli::get_input("Category");
+            if let Some(category) = Category::check(categories, cli::get_input("Category")) {
+                self.category = category;
+            }
         } else if field == "description" {
             self.description = cli::get_input("Description");
+        } else if field == "tags" {
+            self.tags = cli::get_input("Tags")
+                .split_whitespace()
+                .map(String::from)
+                .collect();
         } else {
-            return;
+            cli::fail(&format!("Unknown field: {}", field));
+        }
+    }
+
+    /// fill anything missing here from a duplicate of this Transaction
+    pub fn merge_from(&mut self, other: &Transaction) {
+        if self.category.is_empty() || self.category == "<empty>" {
+            self.category = other.category.clone();
+        }
+        if self.description.is_empty() {
+            self.description = other.description.clone();
+        }
+        if self.import_id.is_empty() {
+            self.import_id = other.import_id.clone();
+        }
+        if self.payee.is_empty() {
+            self.payee = other.payee.clone();
+        }
+    }
+
+    pub fn sort_value(&self, field: &str) -> SortValue {
+        match field {
+            "date" => SortValue::Num(self.date.timestamp() as f64),
+            "amount" => SortValue::Num(self.amount as f64),
+            "account" => SortValue::Text(self.account.clone()),
+            "category" => SortValue::Text(self.category.clone()),
+            "tags" => SortValue::Text(self.tags.join(" ")),
+            _ => SortValue::Text(self.description.clone()),
         }
     }
 
@@ -169,11 +404,19 @@ impl Transaction {
         st.push(',');
         st.push_str(&self.amount.to_string());
         st.push(',');
-        st.push_str(&self.account);
+        st.push_str(&cli::escape_cls(&self.account));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.category));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.description));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.import_id));
+        st.push(',');
+        st.push_str(&cli::escape_cls(&self.tags.join(" ")));
         st.push(',');
-        st.push_str(&self.category);
+        st.push_str(&self.payee);
         st.push(',');
-        st.push_str(&self.description);
+        st.push_str(&self.id.to_string());
         st.push('\n');
         st
     }
@@ -0,0 +1,172 @@
+use crate::cli;
+use crate::data::Data;
+use rustyline::completion::{Completer, Pair};
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::{FileHistory, History};
+use rustyline::validate::Validator;
+use rustyline::{Config, Context, Editor, Helper};
+use std::cell::RefCell;
+use std::io::{self, IsTerminal};
+
+thread_local! {
+    static EDITOR: RefCell<Option<(Editor<Completion, FileHistory>, String)>> = const { RefCell::new(None) };
+}
+
+/// what Tab completes, depending on the prompt
+///     - `$`: command names, then the type abbreviations
+///     - Account, Category and Payee prompts: the existing names
+#[derive(Default)]
+struct Completion {
+    prompt: RefCell<String>,
+    accounts: RefCell<Vec<String>>,
+    categories: RefCell<Vec<String>>,
+    payees: RefCell<Vec<String>>,
+}
+
+impl Completer for Completion {
+    type Candidate = Pair;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<Pair>)> {
+        let prompt = self.prompt.borrow();
+        if prompt.as_str() == "$" {
+            let start = line[..pos].rfind(' ').map_or(0, |space| space + 1);
+            let word = &line[start..pos];
+            let candidates: Vec<&str> = if start == 0 {
+                cli::COMMANDS.to_vec()
+            } else if line[..start].split_whitespace().count() == 1
+                && matches!(
+                    line.split_whitespace().next(),
+                    Some("a" | "e" | "d" | "m" | "l" | "cols")
+                )
+            {
+                Data::DATA_TYPES.to_vec()
+            } else {
+                Vec::new()
+            };
+            return Ok((start, pairs(candidates.into_iter(), word)));
+        }
+        // names may contain spaces, so complete the whole answer
+        let names = if prompt.contains("Account") {
+            self.accounts.borrow()
+        } else if prompt.contains("Category") {
+            self.categories.borrow()
+        } else if prompt.contains("Payee") {
+            self.payees.borrow()
+        } else {
+            return Ok((0, Vec::new()));
+        };
+        Ok((0, pairs(names.iter().map(String::as_str), &line[..pos])))
+    }
+}
+
+impl Hinter for Completion {
+    type Hint = String;
+}
+
+impl Highlighter for Completion {}
+
+impl Validator for Completion {}
+
+impl Helper for Completion {}
+
+/// candidates starting with word, ignoring case
+fn pairs<'a>(candidates: impl Iterator<Item = &'a str>, word: &str) -> Vec<Pair> {
+    let word = word.to_lowercase();
+    candidates
+        .filter(|candidate| candidate.to_lowercase().starts_with(&word))
+        .map(|candidate| Pair {
+            display: candidate.to_string(),
+            replacement: candidate.to_string(),
+        })
+        .collect()
+}
+
+/// read prompts with line editing, Tab completion and the command history in history_path
+///     - only when stdin is a terminal, piped input is read line by line as before
+pub fn start(history_path: String) {
+    if !io::stdin().is_terminal() {
+        return;
+    }
+    let config = Config::builder().auto_add_history(false).build();
+    let mut editor = match Editor::with_config(config) {
+        Ok(editor) => editor,
+        Err(e) => {
+            eprintln!("Error starting the line editor: {}", e);
+            return;
+        }
+    };
+    editor.set_helper(Some(Completion::default()));
+    // there is no history yet the first time
+    let _ = editor.load_history(&history_path);
+    EDITOR.with(|cell| *cell.borrow_mut() = Some((editor, history_path)));
+}
+
+/// save the command history
+pub fn finish() {
+    EDITOR.with(|cell| {
+        if let Some((mut editor, history_path)) = cell.borrow_mut().take() {
+            if let Err(e) = editor.save_history(&history_path) {
+                eprintln!("Error saving command history: {}", e);
+            }
+        }
+    });
+}
+
+/// the names Tab completes at the Account, Category and Payee prompts
+pub fn set_names(data: &Data) {
+    EDITOR.with(|cell| {
+        if let Some((editor, _)) = cell.borrow_mut().as_mut() {
+            if let Some(completion) = editor.helper_mut() {
+                *completion.accounts.borrow_mut() = data
+                    .accounts
+                    .iter()
+                    .map(|acc| acc.get_name().to_string())
+                    .collect();
+                *completion.categories.borrow_mut() = data
+                    .categories
+                    .iter()
+                    .map(|cat| cat.get_name().to_string())
+                    .collect();
+                *completion.payees.borrow_mut() = data
+                    .payees
+                    .iter()
+                    .map(|payee| payee.get_name().to_string())
+                    .collect();
+            }
+        }
+    });
+}
+
+/// read one line with the editor
+///     - None if the editor is not running, Some(None) at the end of input (Ctrl-D)
+///     - Ctrl-C clears the line
+pub fn read_line(prompt: &str) -> Option<Option<String>> {
+    EDITOR.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let (editor, _) = cell.as_mut()?;
+        if let Some(completion) = editor.helper_mut() {
+            *completion.prompt.borrow_mut() = prompt.to_string();
+        }
+        match editor.readline(&format!("{}: ", prompt)) {
+            Ok(line) => {
+                if prompt == "$" && !line.trim().is_empty() {
+                    let _ = editor.history_mut().add(line.trim());
+                }
+                Some(Some(line))
+            }
+            Err(ReadlineError::Interrupted) => Some(Some(String::new())),
+            Err(ReadlineError::Eof) => Some(None),
+            Err(e) => {
+                eprintln!("Error getting input: {}", e);
+                Some(Some(String::new()))
+            }
+        }
+    })
+}
@@ -0,0 +1,165 @@
+use crate::cli;
+use crate::data::{filter::Filter, Data};
+use chrono::{Datelike, Utc};
+use std::fs;
+
+pub mod beancount;
+pub mod ledger;
+pub mod ofx;
+pub mod qif;
+
+/// first and last (year, month) of an export
+type MonthRange = ((i32, u32), (i32, u32));
+
+/// import a statement file into data
+///     - expects args of the form `<format> <path>`
+///     - OFX balances are checked with the saved months before the open one
+pub fn import(data: &mut Data, open_root: &str, arg: &str) {
+    let (format, path) = match split_format(arg) {
+        Some(parts) => parts,
+        None => {
+            cli::fail("Expected a format and a path, e.g. `i ofx ~/statement.qfx`");
+            return;
+        }
+    };
+    let contents = match fs::read_to_string(expand_home(path)) {
+        Ok(contents) => contents,
+        Err(e) => {
+            cli::fail(&format!("Error reading {}: {}", path, e));
+            return;
+        }
+    };
+    match format {
+        "ofx" | "qfx" => {
+            let earlier = match crate::month_of(open_root) {
+                Some(year_month) => {
+                    crate::load_range((0, 1), previous_month(year_month), open_root, data)
+                }
+                None => Vec::new(),
+            };
+            ofx::import(data, &contents, &earlier)
+        }
+        "qif" => qif::import(data, &contents),
+        "ledger" | "hledger" | "journal" => ledger::import(data, &contents),
+        _ => cli::fail(&format!("Unsupported import format: {}", format)),
+    }
+}
+
+/// write data out to a file in another format
+///     - expects args of the form `<format> <path> [YYYY/MM..YYYY/MM] [where <filter>]`
+///     - without a range only the open month is written
+///     - with a filter only the matching Transactions are written, see `Filter`
+pub fn export(data: &Data, open_root: &str, arg: &str) {
+    let (arg, filter) = match arg.split_once(" where ") {
+        Some((arg, query)) => match Filter::parse(query) {
+            Ok(filter) => (arg, filter),
+            Err(e) => {
+                cli::fail(&e);
+                return;
+            }
+        },
+        None => (arg, Filter::All),
+    };
+    let (format, rest) = match split_format(arg) {
+        Some(parts) => parts,
+        None => {
+            cli::fail("Expected a format and a path, e.g. `x qif ~/october.qif`");
+            return;
+        }
+    };
+    let (path, range) = split_range(rest);
+    // a path with spaces comes back quoted so the filter keeps its own quotes
+    let path = path.trim_matches(|c| c == '"' || c == '\'');
+    let mut months = match range {
+        Some((from, to)) => crate::load_range(from, to, open_root, data),
+        None => match crate::month_of(open_root) {
+            Some(year_month) => vec![(year_month, data.clone())],
+            None => vec![(latest_month(data), data.clone())],
+        },
+    };
+    for (_, month) in months.iter_mut() {
+        month.transactions.retain(|tra| filter.matches(tra));
+    }
+    let mut merged = Data::new();
+    for (_, month) in &months {
+        merged.merge(month.clone());
+    }
+    let data = &merged;
+    let contents = match format {
+        "qif" => qif::export(data),
+        "ledger" | "hledger" | "journal" => ledger::export(data),
+        "beancount" | "bean" => beancount::export(&months),
+        _ => {
+            cli::fail(&format!("Unsupported export format: {}", format));
+            return;
+        }
+    };
+    match fs::write(expand_home(path), contents) {
+        Ok(_) => note!(
+            "Exported {} transactions to {}",
+            data.transactions.len(),
+            path
+        ),
+        Err(e) => cli::fail(&format!("Error writing {}: {}", path, e)),
+    }
+}
+
+/// payee/name and memo joined, skipping the memo when it only repeats the name
+pub fn join_description(name: &str, memo: &str) -> String {
+    if memo.is_empty() || memo == name {
+        name.to_string()
+    } else if name.is_empty() {
+        memo.to_string()
+    } else {
+        format!("{} - {}", name, memo)
+    }
+}
+
+fn split_format(arg: &str) -> Option<(&str, &str)> {
+    let (format, path) = arg.split_once(' ')?;
+    Some((format, path.trim()))
+}
+
+/// the (year, month) of the newest Transaction, or of today for an empty month
+fn latest_month(data: &Data) -> (i32, u32) {
+    let date = data
+        .transactions
+        .iter()
+        .map(|tra| tra.get_date_time())
+        .max()
+        .unwrap_or_else(Utc::now);
+    (date.year(), date.month())
+}
+
+fn previous_month((year, month): (i32, u32)) -> (i32, u32) {
+    if month == 1 {
+        (year - 1, 12)
+    } else {
+        (year, month - 1)
+    }
+}
+
+/// split a trailing `YYYY/MM..YYYY/MM` range off the path
+fn split_range(rest: &str) -> (&str, Option<MonthRange>) {
+    if let Some((path, last)) = rest.rsplit_once(' ') {
+        if let Some((from, to)) = last.split_once("..") {
+            if let (Some(from), Some(to)) = (parse_year_month(from), parse_year_month(to)) {
+                return (path.trim(), Some((from, to)));
+            }
+        }
+    }
+    (rest, None)
+}
+
+fn parse_year_month(value: &str) -> Option<(i32, u32)> {
+    let (year, month) = value.split_once(['/', '-'])?;
+    Some((year.parse().ok()?, month.parse().ok()?))
+}
+
+/// replace a leading `~` with the home directory
+fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), home::home_dir()) {
+        (Some(rest), Some(home)) => format!("{}{}", home.display(), rest),
+        _ => path.to_string(),
+    }
+}
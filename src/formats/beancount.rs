@@ -0,0 +1,230 @@
+use crate::data::{transaction::Transaction, Data};
+use crate::formats::ledger;
+use chrono::NaiveDate;
+use std::collections::HashMap;
+
+const CURRENCY: &str = "USD";
+
+/// write the months as a beancount file
+///     - every Account and Category is opened on the first day of the first month
+///     - each month ends with a `balance` assertion per Account, built from the Account values
+///       `Data::update` computes and carried over from month to month
+pub fn export(months: &[((i32, u32), Data)]) -> String {
+    let mut merged = Data::new();
+    for (_, month) in months {
+        merged.merge(month.clone());
+    }
+    let first_month = months
+        .first()
+        .map(|(year_month, _)| *year_month)
+        .unwrap_or((1970, 1));
+    let mut start = first_day(first_month);
+    for tra in &merged.transactions {
+        start = start.min(tra.get_date_time().date_naive());
+    }
+
+    let mut bean = String::from("; exported by budgeters\n");
+    bean.push_str(&format!(
+        "option \"operating_currency\" \"{}\"\n\n",
+        CURRENCY
+    ));
+    let mut opened: Vec<String> = merged
+        .accounts
+        .iter()
+        .map(|acc| account_name(acc.get_name()))
+        .collect();
+    let categories = merged
+        .categories
+        .iter()
+        .map(|cat| category_name(&merged, cat.get_name(), 0.0));
+    let used = merged.transactions.iter().flat_map(|tra| {
+        vec![
+            account_name(tra.get_account()),
+            category_name(&merged, tra.get_category(), tra.get_amount()),
+        ]
+    });
+    for name in categories.chain(used) {
+        if !opened.contains(&name) {
+            opened.push(name);
+        }
+    }
+    for name in opened {
+        bean.push_str(&format!("{} open {}\n", start, name));
+    }
+
+    let mut balances: HashMap<String, f32> = HashMap::new();
+    for ((year, month), data) in months {
+        let mut data = data.clone();
+        data.update();
+        let mut transactions: Vec<&Transaction> = data.transactions.iter().collect();
+        transactions.sort_by_key(|tra| tra.get_date_time());
+        for tra in transactions {
+            bean.push('\n');
+            bean.push_str(&format!(
+                "{} * \"{}\"\n",
+                tra.get_date_time().format("%Y-%m-%d"),
+                escape(tra.get_description())
+            ));
+            if !tra.get_import_id().is_empty() {
+                bean.push_str(&format!(
+                    "  import_id: \"{}\"\n",
+                    escape(tra.get_import_id())
+                ));
+            }
+            bean.push_str(&posting(&account_name(tra.get_account()), tra.get_amount()));
+            bean.push_str(&posting(
+                &category_name(&merged, tra.get_category(), tra.get_amount()),
+                -tra.get_amount(),
+            ));
+        }
+        // a balance directive checks the balance at the start of its day
+        let (next_year, next_month) = if *month == 12 {
+            (year + 1, 1)
+        } else {
+            (*year, month + 1)
+        };
+        let month_end = first_day((next_year, next_month));
+        if !data.accounts.is_empty() {
+            bean.push('\n');
+        }
+        for acc in &data.accounts {
+            let balance = balances.entry(acc.get_name().to_string()).or_insert(0.0);
+            *balance += acc.get_value();
+            bean.push_str(&format!(
+                "{} balance {:<40} {:.2} {}\n",
+                month_end,
+                account_name(acc.get_name()),
+                balance,
+                CURRENCY
+            ));
+        }
+    }
+    bean
+}
+
+fn first_day((year, month): (i32, u32)) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, 1).unwrap_or_default()
+}
+
+fn account_name(name: &str) -> String {
+    format!(
+        "{}:{}",
+        capitalize(ledger::account_kind(name)),
+        components(name)
+    )
+}
+
+fn category_name(data: &Data, name: &str, amount: f32) -> String {
+    let kind = capitalize(ledger::category_kind(data, name, amount));
+    if name.is_empty() || name == "<empty>" {
+        format!("{}:Unknown", kind)
+    } else {
+        format!("{}:{}", kind, components(name))
+    }
+}
+
+fn posting(account: &str, amount: f32) -> String {
+    format!("  {:<40}  {:.2} {}\n", account, amount, CURRENCY)
+}
+
+/// beancount account components start with a capital letter or digit and hold only
+/// letters, digits and dashes
+fn components(name: &str) -> String {
+    name.split(':')
+        .map(|part| {
+            let cleaned: String = part
+                .split(|c: char| !c.is_alphanumeric() && c != '-')
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<&str>>()
+                .join("-");
+            let cleaned = capitalize(&cleaned);
+            match cleaned.chars().next() {
+                Some(c) if c.is_alphanumeric() => cleaned,
+                _ => format!("X{}", cleaned),
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(":")
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn month(month: u32, rows: &[(f32, &str, &str)]) -> Data {
+        let mut data = Data::new();
+        for (amount, account, category) in rows {
+            data.ensure_account(account);
+            data.transactions.push(Transaction::from_parts(
+                Utc.with_ymd_and_hms(2026, month, 2, 0, 0, 0).unwrap(),
+                *amount,
+                account.to_string(),
+                category.to_string(),
+                String::from("Entry"),
+            ));
+        }
+        data
+    }
+
+    #[test]
+    fn balances_carry_over_between_months() {
+        let months = vec![
+            (
+                (2026, 11),
+                month(
+                    11,
+                    &[(1000.0, "Checking", "Pay"), (-50.0, "Visa Card", "Food")],
+                ),
+            ),
+            (
+                (2026, 12),
+                month(
+                    12,
+                    &[(-200.0, "Checking", "Rent"), (-25.0, "Visa Card", "Food")],
+                ),
+            ),
+        ];
+        let bean = export(&months);
+        let balances: Vec<&str> = bean
+            .lines()
+            .filter(|line| line.contains(" balance "))
+            .collect();
+        assert_eq!(balances.len(), 4);
+        assert!(balances[0].starts_with("2026-12-01 balance Assets:Checking"));
+        assert!(balances[0].ends_with(" 1000.00 USD"));
+        assert!(balances[1].starts_with("2026-12-01 balance Liabilities:Visa-Card"));
+        assert!(balances[1].ends_with(" -50.00 USD"));
+        assert!(balances[2].starts_with("2027-01-01 balance Assets:Checking"));
+        assert!(balances[2].ends_with(" 800.00 USD"));
+        assert!(balances[3].ends_with(" -75.00 USD"));
+    }
+
+    #[test]
+    fn opens_accounts_before_the_first_transaction() {
+        let bean = export(&[((2026, 11), month(11, &[(-5.0, "Checking", "")]))]);
+        assert!(bean.contains("2026-11-01 open Assets:Checking\n"));
+        assert!(bean.contains("2026-11-01 open Expenses:Unknown\n"));
+        assert!(bean.contains("  Expenses:Unknown"));
+    }
+
+    #[test]
+    fn account_components() {
+        assert_eq!(components("my checking"), "My-checking");
+        assert_eq!(components("401k:roth"), "401k:Roth");
+        assert_eq!(components("(old)"), "Old");
+        assert_eq!(components("-x"), "X-x");
+    }
+}
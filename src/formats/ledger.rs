@@ -0,0 +1,453 @@
+use crate::data::{duplicate, transaction::Transaction, Data};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+
+/// words in an Account name that make it a `liabilities:` account
+const LIABILITY_WORDS: [&str; 5] = ["credit", "card", "loan", "mortgage", "liabilit"];
+
+/// the Category used for postings to `expenses:unknown` / `income:unknown`
+const UNKNOWN: &str = "unknown";
+
+/// an indented `account  amount` line of a journal entry
+pub struct Posting {
+    pub account: String,
+    pub amount: Option<f32>,
+}
+
+/// a dated journal entry and its postings
+pub struct Entry {
+    pub date: DateTime<Utc>,
+    pub description: String,
+    pub import_id: String,
+    pub postings: Vec<Posting>,
+}
+
+/// write the Transactions of data as a ledger/hledger journal
+///     - Accounts become `assets:` (or `liabilities:` for cards and loans)
+///     - Categories become `income:` when their expected value is positive, otherwise `expenses:`
+pub fn export(data: &Data) -> String {
+    let mut transactions: Vec<&Transaction> = data.transactions.iter().collect();
+    transactions.sort_by_key(|tra| tra.get_date_time());
+
+    let mut declared: Vec<String> = data
+        .accounts
+        .iter()
+        .map(|acc| account_name(acc.get_name()))
+        .collect();
+    let categories = data
+        .categories
+        .iter()
+        .map(|cat| category_name(data, cat.get_name(), 0.0));
+    let used = transactions.iter().flat_map(|tra| {
+        vec![
+            account_name(tra.get_account()),
+            category_name(data, tra.get_category(), tra.get_amount()),
+        ]
+    });
+    for name in categories.chain(used) {
+        if !declared.contains(&name) {
+            declared.push(name);
+        }
+    }
+
+    let mut journal = String::from("; exported by budgeters\n");
+    for name in declared {
+        journal.push_str(&format!("account {}\n", name));
+    }
+    for tra in transactions {
+        journal.push('\n');
+        journal.push_str(&tra.get_date_time().format("%Y-%m-%d ").to_string());
+        journal.push_str(&clean(tra.get_description()));
+        if !tra.get_import_id().is_empty() {
+            journal.push_str(&format!("  ; import_id:{}", tra.get_import_id()));
+        }
+        journal.push('\n');
+        journal.push_str(&posting(&account_name(tra.get_account()), tra.get_amount()));
+        journal.push_str(&posting(
+            &category_name(data, tra.get_category(), tra.get_amount()),
+            -tra.get_amount(),
+        ));
+    }
+    journal
+}
+
+/// parse the dated entries of a plain journal
+///     - directives, periodic and automated entries are skipped
+pub fn parse(contents: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut in_entry = false;
+    for line in contents.lines() {
+        if line.starts_with(|c: char| c.is_ascii_digit()) {
+            in_entry = false;
+            let (head, comment) = split_comment(line);
+            let (date, rest) = head.split_once(char::is_whitespace).unwrap_or((head, ""));
+            let date = match parse_date(date.split('=').next().unwrap_or(date)) {
+                Some(date) => date,
+                None => continue,
+            };
+            let mut description = rest.trim();
+            description = description
+                .strip_prefix(['*', '!'])
+                .unwrap_or(description)
+                .trim_start();
+            if description.starts_with('(') {
+                if let Some(end) = description.find(')') {
+                    description = description[end + 1..].trim_start();
+                }
+            }
+            entries.push(Entry {
+                date,
+                description: description.to_string(),
+                import_id: import_id_tag(comment),
+                postings: Vec::new(),
+            });
+            in_entry = true;
+        } else if in_entry && line.starts_with([' ', '\t']) {
+            let entry = entries.last_mut().unwrap();
+            let (body, comment) = split_comment(line.trim());
+            if entry.import_id.is_empty() {
+                entry.import_id = import_id_tag(comment);
+            }
+            if body.is_empty() {
+                continue;
+            }
+            let (account, amount) = match body.find("  ").or_else(|| body.find('\t')) {
+                Some(split) => (&body[..split], body[split..].trim()),
+                None => (body, ""),
+            };
+            let amount = amount.split('=').next().unwrap_or("").trim();
+            let amount = match parse_amount(amount) {
+                Ok(amount) => amount,
+                Err(e) => {
+                    eprintln!("Skipping entry {}: {}", entry.description, e);
+                    entries.pop();
+                    in_entry = false;
+                    continue;
+                }
+            };
+            entry.postings.push(Posting {
+                account: account.trim_matches(['(', ')', '[', ']']).to_string(),
+                amount,
+            });
+        } else if !line.trim().is_empty() {
+            in_entry = false;
+        }
+    }
+    entries
+}
+
+/// add the entries of a journal to data
+///     - `assets:`/`liabilities:` postings become the Account, `expenses:`/`income:` postings
+///       become the Category, one Transaction per Category posting
+///     - entries carrying an `import_id:` tag already on the Account are skipped
+pub fn import(data: &mut Data, contents: &str) {
+    let mut added = 0;
+    let mut skipped = 0;
+    let mut new_categories = 0;
+    let mut policy = duplicate::Policy::Ask;
+    for mut entry in parse(contents) {
+        let elided: Vec<usize> = (0..entry.postings.len())
+            .filter(|&i| entry.postings[i].amount.is_none())
+            .collect();
+        if elided.len() == 1 {
+            let total: f32 = entry.postings.iter().filter_map(|p| p.amount).sum();
+            entry.postings[elided[0]].amount = Some(-total);
+        }
+        let mut accounts = Vec::new();
+        let mut categories = Vec::new();
+        for posting in &entry.postings {
+            let (kind, name) = posting
+                .account
+                .split_once(':')
+                .unwrap_or((posting.account.as_str(), ""));
+            let amount = posting.amount.unwrap_or(0.0);
+            match kind.to_lowercase().as_str() {
+                // a bare `assets` posting is named after its top level
+                "assets" | "asset" | "liabilities" | "liability" if name.is_empty() => {
+                    accounts.push((kind.to_string(), amount))
+                }
+                "assets" | "asset" | "liabilities" | "liability" => {
+                    accounts.push((name.to_string(), amount))
+                }
+                "expenses" | "expense" | "income" | "revenue" | "revenues" => {
+                    categories.push((name.to_string(), amount))
+                }
+                _ => (),
+            }
+        }
+        let account = match accounts.first() {
+            Some((account, _)) => account.clone(),
+            None => continue,
+        };
+        if !entry.import_id.is_empty()
+            && data
+                .transactions
+                .iter()
+                .any(|tra| tra.get_account() == account && tra.get_import_id() == entry.import_id)
+        {
+            skipped += 1;
+            continue;
+        }
+        let rows: Vec<(String, f32, String)> = if categories.is_empty() {
+            accounts
+                .into_iter()
+                .map(|(account, amount)| (account, amount, String::from("<empty>")))
+                .collect()
+        } else {
+            categories
+                .into_iter()
+                .map(|(category, amount)| {
+                    let category = if category.is_empty() || category == UNKNOWN {
+                        String::from("<empty>")
+                    } else {
+                        category
+                    };
+                    (account.clone(), -amount, category)
+                })
+                .collect()
+        };
+        for (account, amount, category) in rows {
+            data.ensure_account(&account);
+            if data.ensure_category(&category) {
+                new_categories += 1;
+            }
+            let mut tra = Transaction::from_parts(
+                entry.date,
+                amount,
+                account,
+                category,
+                entry.description.clone(),
+            );
+            tra.set_import_id(entry.import_id.clone());
+            data.categorize(&mut tra);
+            data.link_payee(&mut tra);
+            if data.push_checked(tra, &mut policy) {
+                added += 1;
+            } else {
+                skipped += 1;
+            }
+        }
+    }
+    note!(
+        "Imported {} transactions ({} already present or duplicates)",
+        added,
+        skipped
+    );
+    if new_categories > 0 {
+        note!("Added {} new Categories", new_categories);
+    }
+}
+
+fn account_name(name: &str) -> String {
+    format!("{}:{}", account_kind(name), clean(name))
+}
+
+fn category_name(data: &Data, name: &str, amount: f32) -> String {
+    if name.is_empty() || name == "<empty>" {
+        format!("{}:{}", category_kind(data, name, amount), UNKNOWN)
+    } else {
+        format!("{}:{}", category_kind(data, name, amount), clean(name))
+    }
+}
+
+/// `liabilities` for Accounts named like cards and loans, `assets` otherwise
+pub fn account_kind(name: &str) -> &'static str {
+    let lower = name.to_lowercase();
+    if LIABILITY_WORDS.iter().any(|word| lower.contains(word)) {
+        "liabilities"
+    } else {
+        "assets"
+    }
+}
+
+/// `income` or `expenses` for a Category
+///     - by the sign of its expected value, or of its transactions' total when nothing is expected
+///     - `<empty>` goes by the sign of amount
+pub fn category_kind(data: &Data, name: &str, amount: f32) -> &'static str {
+    let income = if name.is_empty() || name == "<empty>" {
+        amount > 0.0
+    } else {
+        let expected = data
+            .categories
+            .iter()
+            .find(|cat| cat.get_name() == name)
+            .map_or(0.0, |cat| *cat.get_expected());
+        if expected != 0.0 {
+            expected > 0.0
+        } else {
+            data.transactions
+                .iter()
+                .filter(|tra| tra.get_category() == name)
+                .map(|tra| tra.get_amount())
+                .sum::<f32>()
+                > 0.0
+        }
+    };
+    if income {
+        "income"
+    } else {
+        "expenses"
+    }
+}
+
+fn posting(account: &str, amount: f32) -> String {
+    format!("    {:<40}  ${:.2}\n", account, amount)
+}
+
+/// two spaces end an account name in a journal, so collapse runs of whitespace
+fn clean(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn split_comment(line: &str) -> (&str, &str) {
+    match line.find(';') {
+        Some(start) => (line[..start].trim_end(), &line[start + 1..]),
+        None => (line, ""),
+    }
+}
+
+fn import_id_tag(comment: &str) -> String {
+    match comment.find("import_id:") {
+        Some(start) => comment[start + "import_id:".len()..]
+            .split([',', ' ', '\t'])
+            .next()
+            .unwrap_or("")
+            .to_string(),
+        None => String::new(),
+    }
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let date = ["%Y-%m-%d", "%Y/%m/%d", "%Y.%m.%d"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())?;
+    Some(Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)))
+}
+
+/// read an amount like `$-12.50`, `-$1,200.00`, `12.50 USD` or `USD 12.50`
+///     - Ok(None) when there is none, so the posting balances the entry
+///     - anything else, such as a price (`10 AAPL @ $150`) or a decimal comma (`1.200,00`), is
+///       an error
+fn parse_amount(value: &str) -> Result<Option<f32>, String> {
+    let tokens: Vec<&str> = value.split_whitespace().collect();
+    let number = match tokens[..] {
+        [] => return Ok(None),
+        [number] => parse_number(number),
+        [number, commodity] | [commodity, number] if is_commodity(commodity) => {
+            parse_number(number)
+        }
+        _ => None,
+    };
+    match number {
+        Some(number) => Ok(Some(number)),
+        None => Err(format!("can't read the amount {}", value)),
+    }
+}
+
+/// a number with an optional sign and currency symbol, and `,` between groups of three digits
+fn parse_number(token: &str) -> Option<f32> {
+    let mut negative = false;
+    let mut rest = token;
+    while let Some(c) = rest.chars().next().filter(|c| "-+$€£¥".contains(*c)) {
+        negative ^= c == '-';
+        rest = &rest[c.len_utf8()..];
+    }
+    let (whole, fraction) = rest.split_once('.').unwrap_or((rest, ""));
+    let groups: Vec<&str> = whole.split(',').collect();
+    let grouped = groups[0].len() <= 3 || groups.len() == 1;
+    if whole.is_empty()
+        || !grouped
+        || groups
+            .iter()
+            .any(|group| !group.chars().all(|c| c.is_ascii_digit()))
+        || groups[1..].iter().any(|group| group.len() != 3)
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+    {
+        return None;
+    }
+    let number: f32 = format!("{}.{}0", groups.concat(), fraction).parse().ok()?;
+    Some(if negative { -number } else { number })
+}
+
+/// a commodity written next to an amount, e.g. `USD`
+fn is_commodity(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(|c| c.is_alphabetic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_numbers() {
+        assert_eq!(parse_number("12.50"), Some(12.5));
+        assert_eq!(parse_number("$-12.50"), Some(-12.5));
+        assert_eq!(parse_number("-$1,200.00"), Some(-1200.0));
+        assert_eq!(parse_number("€1,234,567"), Some(1_234_567.0));
+        assert_eq!(parse_number("+5"), Some(5.0));
+        assert_eq!(parse_number("1200,00"), None);
+        assert_eq!(parse_number("1,20"), None);
+        assert_eq!(parse_number("12.5a"), None);
+        assert_eq!(parse_number("$"), None);
+    }
+
+    #[test]
+    fn parse_amounts() {
+        assert_eq!(parse_amount(""), Ok(None));
+        assert_eq!(parse_amount("$-12.50"), Ok(Some(-12.5)));
+        assert_eq!(parse_amount("12.50 USD"), Ok(Some(12.5)));
+        assert_eq!(parse_amount("USD 12.50"), Ok(Some(12.5)));
+        assert!(parse_amount("10 AAPL @ $150").is_err());
+        assert!(parse_amount("1.200,00 EUR").is_err());
+    }
+
+    #[test]
+    fn parse_entries() {
+        let journal = "; a comment
+account assets:Checking
+
+2026-10-03 * (1042) Corner Cafe  ; import_id:abc
+    expenses:Dining  $12.50
+    assets:Checking
+
+2026/10/05=2026/10/06 Payroll
+    ; import_id:pay-1
+    assets:Checking  1,500.00 USD
+    income:Salary
+
+2026-10-06 Broker
+    assets:Brokerage  10 AAPL @ $150
+    assets:Checking
+
+~ monthly
+    expenses:Rent  $900
+";
+        let entries = parse(journal);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].description, "Corner Cafe");
+        assert_eq!(entries[0].import_id, "abc");
+        assert_eq!(entries[0].postings.len(), 2);
+        assert_eq!(entries[0].postings[0].account, "expenses:Dining");
+        assert_eq!(entries[0].postings[0].amount, Some(12.5));
+        assert_eq!(entries[0].postings[1].amount, None);
+        assert_eq!(entries[1].description, "Payroll");
+        assert_eq!(entries[1].import_id, "pay-1");
+        assert_eq!(entries[1].postings[0].amount, Some(1500.0));
+    }
+
+    #[test]
+    fn import_names_bare_accounts_after_their_top_level() {
+        let mut data = Data::new();
+        import(
+            &mut data,
+            "2026-10-03 Cash spent
+    expenses:Dining  $8.00
+    assets
+",
+        );
+        assert_eq!(data.accounts.len(), 1);
+        assert_eq!(data.accounts[0].get_name(), "assets");
+        assert_eq!(data.transactions[0].get_account(), "assets");
+        assert_eq!(data.transactions[0].get_category(), "Dining");
+        assert_eq!(data.transactions[0].get_amount(), -8.0);
+    }
+}
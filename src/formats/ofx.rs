@@ -0,0 +1,371 @@
+use crate::cli;
+use crate::data::{duplicate, transaction::Transaction, Data};
+use crate::formats;
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+
+/// a single STMTTRN entry of a statement
+pub struct OfxTransaction {
+    pub fitid: String,
+    pub date: Option<DateTime<Utc>>,
+    /// None when the TRNAMT is missing or can't be read
+    pub amount: Option<f32>,
+    pub name: String,
+    pub memo: String,
+}
+
+/// a bank (STMTRS) or credit card (CCSTMTRS) statement
+pub struct Statement {
+    pub account_id: String,
+    pub ledger_balance: Option<f32>,
+    pub ledger_date: Option<DateTime<Utc>>,
+    pub transactions: Vec<OfxTransaction>,
+}
+
+impl OfxTransaction {
+    fn new() -> OfxTransaction {
+        OfxTransaction {
+            fitid: String::new(),
+            date: None,
+            amount: None,
+            name: String::new(),
+            memo: String::new(),
+        }
+    }
+
+    pub fn description(&self) -> String {
+        formats::join_description(&self.name, &self.memo)
+    }
+}
+
+impl Statement {
+    fn new() -> Statement {
+        Statement {
+            account_id: String::new(),
+            ledger_balance: None,
+            ledger_date: None,
+            transactions: Vec::new(),
+        }
+    }
+}
+
+/// parse the statements out of an OFX 1.x (SGML) or 2.x (XML) / QFX file
+pub fn parse(contents: &str) -> Vec<Statement> {
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut open: Vec<String> = Vec::new();
+    let mut current: Option<OfxTransaction> = None;
+    let mut rest = match contents.find("<OFX>") {
+        Some(start) => &contents[start..],
+        None => return statements,
+    };
+    while let Some(start) = rest.find('<') {
+        let after = &rest[start + 1..];
+        let end = match after.find('>') {
+            Some(end) => end,
+            None => break,
+        };
+        let tag = after[..end].trim().to_uppercase();
+        let tail = &after[end + 1..];
+        let next = tail.find('<').unwrap_or(tail.len());
+        let value = decode(tail[..next].trim());
+        rest = &tail[next..];
+
+        if let Some(closing) = tag.strip_prefix('/') {
+            // SGML leaves have no closing tag, so only aggregates are tracked
+            if let Some(pos) = open.iter().rposition(|t| t == closing) {
+                open.truncate(pos);
+            }
+            if closing == "STMTTRN" {
+                if let Some(tra) = current.take() {
+                    last_statement(&mut statements).transactions.push(tra);
+                }
+            }
+            continue;
+        }
+        if value.is_empty() {
+            match tag.as_str() {
+                "STMTRS" | "CCSTMTRS" => statements.push(Statement::new()),
+                "STMTTRN" => current = Some(OfxTransaction::new()),
+                _ => (),
+            }
+            open.push(tag);
+            continue;
+        }
+        if let Some(tra) = current.as_mut() {
+            match tag.as_str() {
+                "FITID" => tra.fitid = value,
+                "DTPOSTED" => tra.date = parse_date(&value),
+                "TRNAMT" => tra.amount = parse_amount(&value),
+                "NAME" => tra.name = value,
+                "MEMO" => tra.memo = value,
+                _ => (),
+            }
+        } else if open.iter().any(|t| t == "LEDGERBAL") {
+            match tag.as_str() {
+                "BALAMT" => last_statement(&mut statements).ledger_balance = parse_amount(&value),
+                "DTASOF" => last_statement(&mut statements).ledger_date = parse_date(&value),
+                _ => (),
+            }
+        } else if tag == "ACCTID" {
+            last_statement(&mut statements).account_id = value;
+        }
+    }
+    statements
+}
+
+/// add the statements in contents to data
+///     - entries whose FITID is already on the account are skipped
+///     - the ledger balance is compared against the account's balance, carried over from the
+///       earlier months
+pub fn import(data: &mut Data, contents: &str, earlier: &[((i32, u32), Data)]) {
+    let statements = parse(contents);
+    if statements.is_empty() {
+        eprintln!("No statements found in OFX file");
+        return;
+    }
+    for statement in statements {
+        let account = match_account(data, &statement.account_id);
+        if account.is_empty() {
+            eprintln!(
+                "Skipping statement {} without an Account",
+                statement.account_id
+            );
+            continue;
+        }
+        let mut added = 0;
+        let mut skipped = 0;
+        let mut policy = duplicate::Policy::Ask;
+        for entry in statement.transactions {
+            let date = match entry.date {
+                Some(date) => date,
+                None => {
+                    eprintln!("Skipping entry {} without a posted date", entry.fitid);
+                    continue;
+                }
+            };
+            let amount = match entry.amount {
+                Some(amount) => amount,
+                None => {
+                    eprintln!("Skipping entry {} without a readable amount", entry.fitid);
+                    continue;
+                }
+            };
+            if !entry.fitid.is_empty()
+                && data
+                    .transactions
+                    .iter()
+                    .any(|tra| tra.get_account() == account && tra.get_import_id() == entry.fitid)
+            {
+                skipped += 1;
+                continue;
+            }
+            let mut tra = Transaction::from_parts(
+                date,
+                amount,
+                account.clone(),
+                String::from("<empty>"),
+                entry.description(),
+            );
+            tra.set_import_id(entry.fitid);
+            data.categorize(&mut tra);
+            data.link_payee(&mut tra);
+            if data.push_checked(tra, &mut policy) {
+                added += 1;
+            } else {
+                skipped += 1;
+            }
+        }
+        note!(
+            "Imported {} transactions into {} ({} already present or duplicates)",
+            added,
+            account,
+            skipped
+        );
+        if let Some(balance) = statement.ledger_balance {
+            let opening = opening_balance(earlier, &account);
+            check_balance(data, &account, opening, balance, statement.ledger_date);
+        }
+    }
+}
+
+/// the account's balance at the end of the earlier months, from their transactions
+fn opening_balance(earlier: &[((i32, u32), Data)], account: &str) -> f32 {
+    earlier
+        .iter()
+        .flat_map(|(_, month)| month.transactions.iter())
+        .filter(|tra| tra.get_account() == account)
+        .map(|tra| tra.get_amount())
+        .sum()
+}
+
+/// compare the statement's ledger balance with the account's opening balance plus its
+/// transactions this month
+fn check_balance(
+    data: &Data,
+    account: &str,
+    opening: f32,
+    balance: f32,
+    as_of: Option<DateTime<Utc>>,
+) {
+    let total: f32 = data
+        .transactions
+        .iter()
+        .filter(|tra| tra.get_account() == account)
+        .map(|tra| tra.get_amount())
+        .sum();
+    let total = cli::money_round(opening + total);
+    let as_of = match as_of {
+        Some(date) => date.format("%m/%d/%Y").to_string(),
+        None => String::from("statement date"),
+    };
+    if (total - balance).abs() < 0.005 {
+        note!(
+            "{} matches the ledger balance of ${} as of {}",
+            account,
+            balance,
+            as_of
+        );
+    } else {
+        note!(
+            "{} has a balance of ${} but the ledger balance is ${} as of {} (off by ${})",
+            account,
+            total,
+            balance,
+            as_of,
+            cli::money_round(balance - total)
+        );
+    }
+}
+
+/// pick the only Account whose name contains the last four digits of the ACCTID,
+/// otherwise ask for one
+fn match_account(data: &Data, account_id: &str) -> String {
+    let digits: String = account_id.chars().filter(|c| c.is_ascii_digit()).collect();
+    if digits.len() >= 4 {
+        let last_four = &digits[digits.len() - 4..];
+        let matches: Vec<&str> = data
+            .accounts
+            .iter()
+            .map(|acc| acc.get_name())
+            .filter(|name| name.contains(last_four))
+            .collect();
+        if matches.len() == 1 {
+            note!("Matched statement for ...{} to {}", last_four, matches[0]);
+            return matches[0].to_string();
+        }
+    }
+    cli::get_input(&format!("Account for statement {}", account_id))
+}
+
+fn last_statement(statements: &mut Vec<Statement>) -> &mut Statement {
+    if statements.is_empty() {
+        statements.push(Statement::new());
+    }
+    statements.last_mut().unwrap()
+}
+
+/// OFX dates look like YYYYMMDD[HHMMSS[.XXX][TZ]], only the day is kept
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let day = value.get(..8)?;
+    let date = NaiveDate::parse_from_str(day, "%Y%m%d").ok()?;
+    Some(Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)))
+}
+
+/// an amount such as `-12.50`, or `-12,50` with a decimal comma
+///     - None if it can't be read
+fn parse_amount(value: &str) -> Option<f32> {
+    value.replace(',', ".").parse().ok()
+}
+
+fn decode(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SGML: &str = "OFXHEADER:100
+<OFX>
+<BANKMSGSRSV1><STMTTRNRS><STMTRS>
+<BANKACCTFROM><ACCTID>000123456789</BANKACCTFROM>
+<BANKTRANLIST>
+<STMTTRN><TRNTYPE>DEBIT<DTPOSTED>20261003120000[-5:EST]<TRNAMT>-12,50<FITID>A1<NAME>Corner Cafe<MEMO>Corner Cafe</STMTTRN>
+<STMTTRN><TRNTYPE>CREDIT<DTPOSTED>20261005<TRNAMT>1500.00<FITID>A2<NAME>Payroll<MEMO>ACME &amp; Sons</STMTTRN>
+<STMTTRN><DTPOSTED>20261006<TRNAMT>abc<FITID>A3<NAME>Broken</STMTTRN>
+</BANKTRANLIST>
+<LEDGERBAL><BALAMT>2487.50<DTASOF>20261031</LEDGERBAL>
+</STMTRS></STMTTRNRS></BANKMSGSRSV1>
+</OFX>";
+
+    #[test]
+    fn parse_sgml_statement() {
+        let statements = parse(SGML);
+        assert_eq!(statements.len(), 1);
+        let statement = &statements[0];
+        assert_eq!(statement.account_id, "000123456789");
+        assert_eq!(statement.ledger_balance, Some(2487.5));
+        assert_eq!(
+            statement.ledger_date,
+            Utc.with_ymd_and_hms(2026, 10, 31, 0, 0, 0).single()
+        );
+        let entries = &statement.transactions;
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[0].fitid, "A1");
+        assert_eq!(entries[0].amount, Some(-12.5));
+        assert_eq!(
+            entries[0].date,
+            Utc.with_ymd_and_hms(2026, 10, 3, 0, 0, 0).single()
+        );
+        assert_eq!(entries[0].description(), "Corner Cafe");
+        assert_eq!(entries[1].description(), "Payroll - ACME & Sons");
+        assert_eq!(entries[2].amount, None);
+    }
+
+    #[test]
+    fn parse_xml_credit_card_statement() {
+        let xml = "<?xml version=\"1.0\"?><OFX><CREDITCARDMSGSRSV1><CCSTMTTRNRS><CCSTMTRS>\
+                   <CCACCTFROM><ACCTID>4111222233334444</ACCTID></CCACCTFROM>\
+                   <BANKTRANLIST><STMTTRN><DTPOSTED>20261012</DTPOSTED>\
+                   <TRNAMT>-40.00</TRNAMT><FITID>C1</FITID><NAME>Fuel</NAME></STMTTRN>\
+                   </BANKTRANLIST></CCSTMTRS></CCSTMTTRNRS></CREDITCARDMSGSRSV1></OFX>";
+        let statements = parse(xml);
+        assert_eq!(statements.len(), 1);
+        assert_eq!(statements[0].account_id, "4111222233334444");
+        assert_eq!(statements[0].ledger_balance, None);
+        assert_eq!(statements[0].transactions.len(), 1);
+        assert_eq!(statements[0].transactions[0].amount, Some(-40.0));
+        assert_eq!(statements[0].transactions[0].description(), "Fuel");
+    }
+
+    #[test]
+    fn parse_without_ofx_block() {
+        assert!(parse("not a statement").is_empty());
+    }
+
+    #[test]
+    fn opening_balance_sums_earlier_months() {
+        let tra = |month: u32, amount: f32, account: &str| {
+            Transaction::from_parts(
+                Utc.with_ymd_and_hms(2026, month, 1, 0, 0, 0).unwrap(),
+                amount,
+                account.to_string(),
+                String::from("<empty>"),
+                String::new(),
+            )
+        };
+        let mut august = Data::new();
+        august.transactions = vec![tra(8, 100.0, "Checking"), tra(8, -5.0, "Card")];
+        let mut september = Data::new();
+        september.transactions = vec![tra(9, -25.5, "Checking")];
+        let earlier = vec![((2026, 8), august), ((2026, 9), september)];
+        assert_eq!(opening_balance(&earlier, "Checking"), 74.5);
+        assert_eq!(opening_balance(&earlier, "Savings"), 0.0);
+        assert_eq!(opening_balance(&[], "Checking"), 0.0);
+    }
+}
@@ -0,0 +1,390 @@
+use crate::cli;
+use crate::data::{duplicate, transaction::Transaction, Data};
+use crate::formats::{self, ledger};
+use chrono::{DateTime, NaiveDate, NaiveTime, TimeZone, Utc};
+
+/// the `!Type:` headers whose entries are plain register transactions
+const REGISTER_TYPES: [&str; 5] = ["Bank", "CCard", "Cash", "Oth A", "Oth L"];
+
+/// one S/E/$ group of a split transaction
+pub struct QifSplit {
+    pub category: String,
+    pub memo: String,
+    pub amount: f32,
+}
+
+/// a single `^`-terminated entry
+pub struct QifTransaction {
+    pub date: Option<DateTime<Utc>>,
+    pub amount: f32,
+    pub payee: String,
+    pub memo: String,
+    pub category: String,
+    pub splits: Vec<QifSplit>,
+}
+
+/// the entries following a `!Type:Bank` (or similar) header
+pub struct Section {
+    pub kind: String,
+    pub account: String,
+    pub transactions: Vec<QifTransaction>,
+}
+
+enum Mode {
+    Skip,
+    Account,
+    Register,
+}
+
+impl QifTransaction {
+    fn new() -> QifTransaction {
+        QifTransaction {
+            date: None,
+            amount: 0.0,
+            payee: String::new(),
+            memo: String::new(),
+            category: String::new(),
+            splits: Vec::new(),
+        }
+    }
+}
+
+/// parse the register sections out of a QIF file
+///     - the account of a section comes from the `!Account` block before it, if any
+pub fn parse(contents: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = Vec::new();
+    let mut mode = Mode::Skip;
+    let mut pending_account = String::new();
+    let mut current = QifTransaction::new();
+    for line in contents.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(header) = line.strip_prefix('!') {
+            if header.eq_ignore_ascii_case("Account") {
+                mode = Mode::Account;
+            } else if let Some(kind) = header.strip_prefix("Type:") {
+                let kind = kind.trim();
+                if REGISTER_TYPES.iter().any(|t| t.eq_ignore_ascii_case(kind)) {
+                    sections.push(Section {
+                        kind: kind.to_string(),
+                        account: std::mem::take(&mut pending_account),
+                        transactions: Vec::new(),
+                    });
+                    mode = Mode::Register;
+                } else {
+                    mode = Mode::Skip;
+                }
+            }
+            // !Option and !Clear lines leave the mode unchanged
+            continue;
+        }
+        let (code, value) = line.split_at(line.chars().next().map_or(1, char::len_utf8));
+        let value = value.trim();
+        match mode {
+            Mode::Skip => (),
+            Mode::Account => {
+                if code == "N" {
+                    pending_account = value.to_string();
+                }
+            }
+            Mode::Register => match code {
+                "^" => {
+                    if let Some(section) = sections.last_mut() {
+                        section
+                            .transactions
+                            .push(std::mem::replace(&mut current, QifTransaction::new()));
+                    }
+                }
+                "D" => current.date = parse_date(value),
+                "T" | "U" => current.amount = parse_amount(value),
+                "P" => current.payee = value.to_string(),
+                "M" => current.memo = value.to_string(),
+                "L" => current.category = value.to_string(),
+                "S" => current.splits.push(QifSplit {
+                    category: value.to_string(),
+                    memo: String::new(),
+                    amount: 0.0,
+                }),
+                "E" => {
+                    if let Some(split) = current.splits.last_mut() {
+                        split.memo = value.to_string();
+                    }
+                }
+                "$" => {
+                    if let Some(split) = current.splits.last_mut() {
+                        split.amount = parse_amount(value);
+                    }
+                }
+                _ => (),
+            },
+        }
+    }
+    sections
+}
+
+/// add the sections in contents to data
+///     - each split becomes its own Transaction
+///     - transfers (`L[Account]`) have no Category, they go to `<empty>`
+///     - Accounts and Categories that don't exist yet are added
+pub fn import(data: &mut Data, contents: &str) {
+    let sections = parse(contents);
+    if sections.is_empty() {
+        eprintln!("No !Type:Bank or !Type:CCard sections found in QIF file");
+        return;
+    }
+    let mut new_categories = 0;
+    for section in sections {
+        let account = if section.account.is_empty() {
+            cli::get_input(&format!("Account for !Type:{} section", section.kind))
+        } else {
+            section.account
+        };
+        if data.ensure_account(&account) {
+            note!("Added new Account {}", account);
+        }
+        let mut added = 0;
+        let mut skipped = 0;
+        let mut policy = duplicate::Policy::Ask;
+        for entry in section.transactions {
+            let date = match entry.date {
+                Some(date) => date,
+                None => {
+                    eprintln!("Skipping entry {} without a date", entry.payee);
+                    continue;
+                }
+            };
+            let mut parts = Vec::new();
+            if entry.splits.is_empty() {
+                parts.push((entry.amount, entry.category.clone(), entry.memo.clone()));
+            } else {
+                for split in &entry.splits {
+                    let memo = if split.memo.is_empty() {
+                        entry.memo.clone()
+                    } else {
+                        split.memo.clone()
+                    };
+                    parts.push((split.amount, split.category.clone(), memo));
+                }
+            }
+            for (amount, category, memo) in parts {
+                let category = if category.is_empty() || category.starts_with('[') {
+                    String::from("<empty>")
+                } else {
+                    category
+                };
+                if data.ensure_category(&category) {
+                    new_categories += 1;
+                }
+                let mut tra = Transaction::from_parts(
+                    date,
+                    amount,
+                    account.clone(),
+                    category,
+                    formats::join_description(&entry.payee, &memo),
+                );
+                data.categorize(&mut tra);
+                data.link_payee(&mut tra);
+                if data.push_checked(tra, &mut policy) {
+                    added += 1;
+                } else {
+                    skipped += 1;
+                }
+            }
+        }
+        note!(
+            "Imported {} transactions into {} ({} duplicates skipped)",
+            added,
+            account,
+            skipped
+        );
+    }
+    if new_categories > 0 {
+        note!("Added {} new Categories", new_categories);
+    }
+}
+
+/// write the Transactions of data as QIF, one section per Account
+///     - the description is split back into payee and memo at ` - `, the way import joins them
+pub fn export(data: &Data) -> String {
+    let mut accounts: Vec<&str> = data.accounts.iter().map(|acc| acc.get_name()).collect();
+    for tra in &data.transactions {
+        if !accounts.contains(&tra.get_account()) {
+            accounts.push(tra.get_account());
+        }
+    }
+    let mut qif = String::new();
+    for account in accounts {
+        let transactions: Vec<&Transaction> = data
+            .transactions
+            .iter()
+            .filter(|tra| tra.get_account() == account)
+            .collect();
+        if transactions.is_empty() {
+            continue;
+        }
+        let kind = account_type(account);
+        qif.push_str(&format!(
+            "!Account\nN{}\nT{}\n^\n!Type:{}\n",
+            account, kind, kind
+        ));
+        for tra in transactions {
+            qif.push_str(&format!("D{}\n", tra.get_date()));
+            qif.push_str(&format!("T{:.2}\n", tra.get_amount()));
+            match tra.get_description().split_once(" - ") {
+                Some((payee, memo)) => qif.push_str(&format!("P{}\nM{}\n", payee, memo)),
+                None => qif.push_str(&format!("P{}\n", tra.get_description())),
+            }
+            if tra.get_category() != "<empty>" && !tra.get_category().is_empty() {
+                qif.push_str(&format!("L{}\n", tra.get_category()));
+            }
+            qif.push_str("^\n");
+        }
+    }
+    qif
+}
+
+/// the `!Type:` of an Account: `CCard` for cards, `Oth L` for loans, `Bank` otherwise
+fn account_type(name: &str) -> &'static str {
+    let lower = name.to_lowercase();
+    if lower.contains("card") || lower.contains("credit") {
+        "CCard"
+    } else if ledger::account_kind(name) == "liabilities" {
+        "Oth L"
+    } else {
+        "Bank"
+    }
+}
+
+/// QIF dates are month first: `10/18/2026`, `10/18/26` or `10/18'26`
+///     - an apostrophe before the year means 20xx
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let modern = value.contains('\'');
+    let cleaned: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut parts = cleaned.split(['/', '\'', '-', '.']);
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let mut year: i32 = parts.next()?.parse().ok()?;
+    if year < 100 {
+        year += if modern || year < 70 { 2000 } else { 1900 };
+    }
+    let date = NaiveDate::from_ymd_opt(year, month, day)?;
+    Some(Utc.from_utc_datetime(&date.and_time(NaiveTime::MIN)))
+}
+
+fn parse_amount(value: &str) -> f32 {
+    value.replace(',', "").parse().unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QIF: &str = "!Option:AutoSwitch
+!Account
+NVisa Card
+TCCard
+^
+!Clear:AutoSwitch
+!Type:CCard
+D10/03'26
+T-1,234.50
+PFurniture Shop
+MSofa
+LHome
+^
+D10/05/2026
+T-60.00
+PGrocer
+SGroceries
+$-45.00
+SHousehold
+EDish soap
+$-15.00
+^
+D10/09/26
+T200.00
+PPayment
+L[Checking]
+^
+!Type:Invst
+D10/10/2026
+T5.00
+^
+";
+
+    #[test]
+    fn parse_sections_and_splits() {
+        let sections = parse(QIF);
+        assert_eq!(sections.len(), 1);
+        let section = &sections[0];
+        assert_eq!(section.kind, "CCard");
+        assert_eq!(section.account, "Visa Card");
+        assert_eq!(section.transactions.len(), 3);
+        let sofa = &section.transactions[0];
+        assert_eq!(sofa.amount, -1234.5);
+        assert_eq!(sofa.payee, "Furniture Shop");
+        assert_eq!(sofa.memo, "Sofa");
+        assert_eq!(sofa.category, "Home");
+        let grocer = &section.transactions[1];
+        assert_eq!(grocer.splits.len(), 2);
+        assert_eq!(grocer.splits[1].category, "Household");
+        assert_eq!(grocer.splits[1].memo, "Dish soap");
+        assert_eq!(grocer.splits[1].amount, -15.0);
+    }
+
+    #[test]
+    fn parse_dates() {
+        let day = |year, month, day| Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).single();
+        assert_eq!(parse_date("10/18/2026"), day(2026, 10, 18));
+        assert_eq!(parse_date("10/18'26"), day(2026, 10, 18));
+        assert_eq!(parse_date("10/18/99"), day(1999, 10, 18));
+        assert_eq!(parse_date("1/ 2/05"), day(2005, 1, 2));
+        assert_eq!(parse_date("13/18/2026"), None);
+        assert_eq!(parse_date("soon"), None);
+    }
+
+    #[test]
+    fn import_splits_and_transfers() {
+        let mut data = Data::new();
+        import(&mut data, QIF);
+        assert_eq!(data.accounts.len(), 1);
+        assert_eq!(data.accounts[0].get_name(), "Visa Card");
+        let rows: Vec<(f32, &str, &str)> = data
+            .transactions
+            .iter()
+            .map(|tra| (tra.get_amount(), tra.get_category(), tra.get_description()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (-1234.5, "Home", "Furniture Shop - Sofa"),
+                (-45.0, "Groceries", "Grocer"),
+                (-15.0, "Household", "Grocer - Dish soap"),
+                (200.0, "<empty>", "Payment"),
+            ]
+        );
+    }
+
+    #[test]
+    fn export_round_trips() {
+        let mut data = Data::new();
+        import(&mut data, QIF);
+        let exported = export(&data);
+        assert!(exported.starts_with("!Account\nNVisa Card\nTCCard\n^\n!Type:CCard\n"));
+        assert!(exported.contains("D10/03/2026\nT-1234.50\nPFurniture Shop\nMSofa\nLHome\n^\n"));
+        assert!(exported.contains("D10/09/2026\nT200.00\nPPayment\n^\n"));
+        let mut again = Data::new();
+        import(&mut again, &exported);
+        assert_eq!(again.transactions.len(), data.transactions.len());
+    }
+
+    #[test]
+    fn account_types() {
+        assert_eq!(account_type("Visa Card"), "CCard");
+        assert_eq!(account_type("Credit Union Visa"), "CCard");
+        assert_eq!(account_type("Car Loan"), "Oth L");
+        assert_eq!(account_type("Checking"), "Bank");
+    }
+}
@@ -0,0 +1,180 @@
+use crate::cli;
+use crate::data::Data;
+
+/// how many steps `u` can go back
+const MAX_STEPS: usize = 100;
+
+/// snapshots of Data taken before each command that changed it
+///     - a step is the command that made it and the Data from before (for undo) or
+///       after (for redo) it ran
+pub struct History {
+    undo: Vec<(String, Data)>,
+    redo: Vec<(String, Data)>,
+}
+
+impl History {
+    pub fn new() -> History {
+        History {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// remember before as the state to return to if the command changed data
+    ///     - a new change forgets everything that was undone
+    pub fn record(&mut self, command: String, before: Data, data: &Data) {
+        if before.fingerprint() == data.fingerprint() {
+            return;
+        }
+        self.undo.push((command, before));
+        if self.undo.len() > MAX_STEPS {
+            self.undo.remove(0);
+        }
+        self.redo.clear();
+    }
+
+    /// go back arg steps (1 if empty)
+    pub fn undo(&mut self, data: &mut Data, arg: &str) {
+        if let Some(steps) = steps(arg) {
+            step(
+                &mut self.undo,
+                &mut self.redo,
+                data,
+                steps,
+                ("undo", "Undid"),
+            );
+        }
+    }
+
+    /// go forward arg undone steps (1 if empty)
+    pub fn redo(&mut self, data: &mut Data, arg: &str) {
+        if let Some(steps) = steps(arg) {
+            step(
+                &mut self.redo,
+                &mut self.undo,
+                data,
+                steps,
+                ("redo", "Redid"),
+            );
+        }
+    }
+}
+
+fn steps(arg: &str) -> Option<usize> {
+    if arg.is_empty() {
+        return Some(1);
+    }
+    match arg.parse() {
+        Ok(steps) => Some(steps),
+        Err(e) => {
+            cli::fail(&format!("Error reading number of steps: {}", e));
+            None
+        }
+    }
+}
+
+/// move steps snapshots from `from` to `to`, swapping each with data
+fn step(
+    from: &mut Vec<(String, Data)>,
+    to: &mut Vec<(String, Data)>,
+    data: &mut Data,
+    steps: usize,
+    (action, done): (&str, &str),
+) {
+    if from.is_empty() {
+        cli::fail(&format!("Nothing to {}", action));
+        return;
+    }
+    for _ in 0..steps {
+        match from.pop() {
+            Some((command, snapshot)) => {
+                to.push((command.clone(), std::mem::replace(data, snapshot)));
+                note!("{} `{}`", done, command);
+            }
+            None => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(data: &Data) -> Vec<&str> {
+        data.accounts.iter().map(|acc| acc.get_name()).collect()
+    }
+
+    /// add an Account to data the way a command would, recording the step
+    fn add(history: &mut History, data: &mut Data, name: &str) {
+        let before = data.clone();
+        data.ensure_account(name);
+        history.record(format!("a acc {}", name), before, data);
+    }
+
+    #[test]
+    fn undo_and_redo_steps() {
+        let mut history = History::new();
+        let mut data = Data::new();
+        add(&mut history, &mut data, "Checking");
+        add(&mut history, &mut data, "Savings");
+        add(&mut history, &mut data, "Cash");
+
+        history.undo(&mut data, "");
+        assert_eq!(names(&data), vec!["Checking", "Savings"]);
+        history.undo(&mut data, "2");
+        assert!(names(&data).is_empty());
+        history.redo(&mut data, "");
+        assert_eq!(names(&data), vec!["Checking"]);
+        history.redo(&mut data, "5");
+        assert_eq!(names(&data), vec!["Checking", "Savings", "Cash"]);
+        assert!(!cli::take_failure());
+    }
+
+    #[test]
+    fn unchanged_commands_are_not_recorded() {
+        let mut history = History::new();
+        let mut data = Data::new();
+        add(&mut history, &mut data, "Checking");
+        add(&mut history, &mut data, "Checking");
+        history.undo(&mut data, "");
+        assert!(names(&data).is_empty());
+        cli::take_failure();
+        history.undo(&mut data, "");
+        assert!(cli::take_failure());
+    }
+
+    #[test]
+    fn a_new_change_forgets_the_undone_steps() {
+        let mut history = History::new();
+        let mut data = Data::new();
+        add(&mut history, &mut data, "Checking");
+        history.undo(&mut data, "");
+        add(&mut history, &mut data, "Cash");
+        cli::take_failure();
+        history.redo(&mut data, "");
+        assert!(cli::take_failure());
+        assert_eq!(names(&data), vec!["Cash"]);
+    }
+
+    #[test]
+    fn keeps_the_last_steps_only() {
+        let mut history = History::new();
+        let mut data = Data::new();
+        for n in 0..MAX_STEPS + 5 {
+            add(&mut history, &mut data, &n.to_string());
+        }
+        history.undo(&mut data, &(MAX_STEPS + 5).to_string());
+        assert_eq!(names(&data).len(), 5);
+    }
+
+    #[test]
+    fn bad_step_counts_fail() {
+        let mut history = History::new();
+        let mut data = Data::new();
+        add(&mut history, &mut data, "Checking");
+        cli::take_failure();
+        history.undo(&mut data, "two");
+        assert!(cli::take_failure());
+        assert_eq!(names(&data), vec!["Checking"]);
+    }
+}
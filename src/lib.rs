@@ -1,17 +1,42 @@
-use home; // for the home_dir function
+use chrono::{Datelike, Local, Month};
+use std::collections::HashMap;
 use std::fs::{self, ReadDir};
+use std::io::{self, BufRead, BufReader, IsTerminal};
 use std::path::{Path, PathBuf};
 
+#[macro_use]
 mod cli;
 mod data;
+mod editor;
+mod formats;
+mod history;
+mod settings;
+#[cfg(unix)]
+mod tui;
+mod view;
 use cli::Command;
 use data::Data;
+use history::History;
+use view::View;
 
-fn setup() -> Data {
-    println!("Setting up...");
+/// files kept in the budget root rather than in a month
+const SHARED_FILES: [&str; 2] = ["Rule.cls", "Payee.cls"];
+
+/// the file whose presence marks a month as closed (read-only)
+const CLOSED_MARKER: &str = ".closed";
+
+fn setup(root: &str) -> Data {
     let mut new_data = Data::new();
-    match fs::read_dir(Path::new(&get_dir_path())) {
+    for shared in SHARED_FILES {
+        let path = format!("{}/{}", budget_root(), shared);
+        if let Ok(contents) = fs::read_to_string(&path) {
+            parse_file(&mut new_data, contents, PathBuf::from(path));
+        }
+    }
+    match fs::read_dir(Path::new(root)) {
         Ok(files) => parse_dir(&mut new_data, files),
+        // a month that was never saved starts out empty
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => new_data,
         Err(e) => {
             eprintln!("Error setting up: {}", e);
             new_data
@@ -19,46 +44,375 @@ fn setup() -> Data {
     }
 }
 
-fn shutdown(data: &Data) {
-    println!("Shutting down...");
-    let root = get_dir_path();
-    // write out Accounts
-    let mut accounts = root.clone();
-    accounts.push_str("/Account.cls");
-    fs::write(Path::new(&accounts), data.to_cls(&accounts)).expect("Failed to save accounts.");
-    let mut categories = root.clone();
-    categories.push_str("/Category.cls");
-    fs::write(Path::new(&categories), data.to_cls(&categories))
-        .expect("Failed to save categories.");
-    let mut transactions = root.clone();
-    transactions.push_str("/Transaction.cls");
-    fs::write(Path::new(&transactions), data.to_cls(&transactions))
-        .expect("Failed to save transactions.");
+/// write the month (and the shared files) back out
+///     - returns false if anything could not be saved
+fn shutdown(data: &Data, root: &str) -> bool {
+    if let Err(e) = fs::create_dir_all(root) {
+        cli::fail(&format!("Failed to create {}: {}", root, e));
+        return false;
+    }
+    let mut saved = true;
+    for file in ["Account.cls", "Category.cls", "Transaction.cls"] {
+        let path = format!("{}/{}", root, file);
+        if let Err(e) = fs::write(Path::new(&path), data.to_cls(&path)) {
+            cli::fail(&format!("Failed to save {}: {}", file, e));
+            saved = false;
+        }
+    }
+    // Rules and Payees apply to every month, so they live in the budget root
+    for shared in SHARED_FILES {
+        let path = format!("{}/{}", budget_root(), shared);
+        if let Err(e) = fs::write(Path::new(&path), data.to_cls(&path)) {
+            cli::fail(&format!("Failed to save {}: {}", shared, e));
+            saved = false;
+        }
+    }
+    saved
+}
+
+/// what to do after a command
+enum Flow {
+    Continue,
+    Save,
+    Discard,
+}
+
+/// the open month and what the prompt remembers while working on it
+struct Session {
+    root: String,
+    data: Data,
+    history: History,
+    view: View,
+    closed: bool,
+}
+
+impl Session {
+    fn open(root: String) -> Session {
+        cli::set_open_month(month_of(&root));
+        Session {
+            data: setup(&root),
+            closed: is_closed(&root),
+            root,
+            history: History::new(),
+            view: View::new(),
+        }
+    }
+
+    /// save the open month and switch to the one named by arg (`YYYY MM`, `YYYY/MM` or asked)
+    ///     - a month that does not exist yet starts out empty, and is created when saved
+    fn switch(&mut self, arg: &str) {
+        let mut globals = HashMap::new();
+        let words: Vec<&str> = arg.split([' ', '/']).filter(|w| !w.is_empty()).collect();
+        match words[..] {
+            [] => {
+                globals.insert(String::from("year"), cli::get_input("Year"));
+                globals.insert(String::from("month"), cli::get_input("Month"));
+            }
+            [year, month] => {
+                globals.insert(String::from("year"), year.to_string());
+                globals.insert(String::from("month"), month.to_string());
+            }
+            _ => {
+                cli::fail("Expected a year and a month, e.g. `--open 2026 11`");
+                return;
+            }
+        }
+        let root = match month_root(&globals) {
+            Ok(root) => root,
+            Err(e) => {
+                cli::fail(&e);
+                return;
+            }
+        };
+        if root == self.root {
+            note!("{} is already open", self.root);
+            return;
+        }
+        if !self.closed && !shutdown(&self.data, &self.root) {
+            cli::fail("Staying in the open month since it could not be saved");
+            return;
+        }
+        let exists = Path::new(&root).exists();
+        let view = std::mem::replace(&mut self.view, View::new());
+        *self = Session::open(root);
+        self.view = view;
+        note!("Opened {}", self.root);
+        if !exists {
+            note!("This is a new month, it is created when saved");
+        } else if self.closed {
+            note!("This month is closed, `--reopen` it to make changes");
+        }
+    }
+
+    /// save the open month and mark it read-only
+    fn close(&mut self) {
+        if self.closed {
+            note!("{} is already closed", self.root);
+            return;
+        }
+        if !shutdown(&self.data, &self.root) {
+            return;
+        }
+        let marker = format!("{}/{}", self.root, CLOSED_MARKER);
+        match fs::write(&marker, format!("{}\n", Local::now().format("%m/%d/%Y"))) {
+            Ok(_) => {
+                self.closed = true;
+                // undoing past the close would change a finished month
+                self.history = History::new();
+                note!("Closed {}, `--reopen` it to make changes", self.root);
+            }
+            Err(e) => cli::fail(&format!("Failed to close {}: {}", self.root, e)),
+        }
+    }
+
+    /// make a closed month editable again
+    fn reopen(&mut self) {
+        if !self.closed {
+            note!("{} is not closed", self.root);
+            return;
+        }
+        let marker = format!("{}/{}", self.root, CLOSED_MARKER);
+        match fs::remove_file(&marker) {
+            Ok(_) => {
+                self.closed = false;
+                note!("Reopened {}", self.root);
+            }
+            Err(e) => cli::fail(&format!("Failed to reopen {}: {}", self.root, e)),
+        }
+    }
+}
+
+/// run one Command against the open month, remembering any change in history
+///     - commands that change the month fail while it is closed
+fn execute(session: &mut Session, command: Command) -> Flow {
+    let changes =
+        command.label().is_some() || matches!(command, Command::Undo(_) | Command::Redo(_));
+    if session.closed && changes {
+        cli::fail(&format!(
+            "{} is closed, `--reopen` it to make changes",
+            session.root
+        ));
+        return Flow::Continue;
+    }
+    let Session {
+        root,
+        data,
+        history,
+        view,
+        ..
+    } = session;
+    let before = command.label().map(|label| (label, data.clone()));
+    match command {
+        Command::Help => cli::print_help(),
+        Command::Cancel => return Flow::Discard,
+        Command::Quit => return Flow::Save,
+        Command::Update => data.update(),
+        Command::Empty => (),
+        Command::Add(ref args) => data.add(args),
+        Command::Edit(ref args) => data.edit(args),
+        Command::Delete(ref args) => data.delete(args),
+        Command::Merge(ref args) => data.merge_into(args),
+        Command::Bulk(ref args) => data.bulk(args, view),
+        Command::Search(ref args) => data.search(args, view),
+        Command::Import(ref args) => formats::import(data, root, args),
+        Command::Export(ref args) => formats::export(data, root, args),
+        Command::Duplicates(ref args) => data.dups(args),
+        Command::Categorize => data.apply_rules(),
+        Command::Rules => data.match_rules(),
+        Command::List(ref args) => data.list(args, view),
+        Command::RollOver(ref args) => data.roll(args), // TODO: data.roll(args)
+        Command::Undo(ref args) => history.undo(data, args),
+        Command::Redo(ref args) => history.redo(data, args),
+        Command::Sort(ref args) => view.sort(args),
+        Command::Columns(ref args) => view::columns(args),
+        Command::Open(ref args) => session.switch(args),
+        Command::Close => session.close(),
+        Command::Reopen => session.reopen(),
+        #[cfg(unix)]
+        Command::Tui => tui::run(session),
+        #[cfg(not(unix))]
+        Command::Tui => cli::fail("The full-screen view needs a terminal"),
+    }
+    if let Some((label, before)) = before {
+        session.history.record(label, before, &session.data);
+    }
+    Flow::Continue
 }
 
 pub fn run() {
-    let mut data = setup();
+    let root = get_dir_path();
+    println!("Setting up...");
+    let mut session = Session::open(root);
+    if session.closed {
+        note!("This month is closed, `--reopen` it to make changes");
+    }
+    editor::start(format!("{}/.history", budget_root()));
     loop {
-        match cli::prompt() {
-            Command::Help => cli::print_help(),
-            Command::Cancel => break,
-            Command::Quit => {
-                shutdown(&mut data);
-                break;
+        editor::set_names(&session.data);
+        let flow = execute(&mut session, cli::prompt());
+        cli::clear_answers();
+        match flow {
+            Flow::Continue => (),
+            Flow::Save => {
+                println!("Shutting down...");
+                if session.closed || shutdown(&session.data, &session.root) {
+                    break;
+                }
+                // with no terminal to answer the prompt, staying would only ask again
+                if !io::stdin().is_terminal() {
+                    break;
+                }
+                cli::fail("Staying at the prompt since the month could not be saved");
+                note!("`--cancel` quits without saving");
             }
-            Command::Update => data.update(),
-            Command::Empty => continue,
-            Command::Add(ref args) => data.add(args),
-            Command::Edit(ref args) => data.edit(args),
-            Command::Delete(ref args) => data.delete(args),
-            Command::Search(ref args) => data.search(args),
-            Command::List(ref args) => data.list(args),
-            Command::RollOver(ref args) => data.roll(args), // TODO: data.roll(args)
+            Flow::Discard => break,
         }
     }
+    editor::finish();
 }
 
-fn get_dir_path() -> String {
+/// run a single command given on the command line, without prompting
+///     - returns the exit status: 0 on success, 1 if the command failed, 2 for bad usage
+///     - the month is saved only if the command changes data (or data was changed in `tui`) and
+///       nothing failed
+pub fn run_command(args: &[String]) -> i32 {
+    let mut invocation = match cli::parse_args(args) {
+        Ok(invocation) => invocation,
+        Err(e) => {
+            eprintln!("{}", e);
+            cli::print_usage();
+            return 2;
+        }
+    };
+    if invocation.command == "batch" {
+        return run_batch(invocation);
+    }
+    // `--output <mode>`, before or after the command, is the `-o <mode>` of `l` and `/`
+    if matches!(invocation.command.as_str(), "l" | "list" | "/" | "search") {
+        let flag = invocation
+            .fields
+            .iter()
+            .position(|(key, _)| key == "output");
+        let output = match flag {
+            Some(index) => Some(invocation.fields.remove(index).1),
+            None => invocation.globals.remove("output"),
+        };
+        if let Some(output) = output {
+            invocation.args.extend([String::from("-o"), output]);
+        }
+    }
+    let command = cli::to_command(&invocation.command, invocation.args);
+    match command {
+        Command::Empty
+        | Command::Cancel
+        | Command::Quit
+        | Command::RollOver(_)
+        | Command::Undo(_)
+        | Command::Redo(_)
+        | Command::Sort(_)
+        | Command::Open(_) => {
+            eprintln!("Unknown command: {}", invocation.command);
+            cli::print_usage();
+            return 2;
+        }
+        Command::Help => {
+            cli::print_usage();
+            return 0;
+        }
+        _ => (),
+    }
+    let root = match month_root(&invocation.globals) {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let changing = command.label().is_some();
+    let mut session = Session::open(root);
+    let before = session.data.fingerprint();
+    cli::take_failure();
+    let create = invocation.fields.iter().any(|(key, _)| key == "create");
+    invocation.fields.retain(|(key, _)| key != "create");
+    cli::add_named(invocation.fields);
+    cli::set_strict(true);
+    cli::set_create(create);
+    execute(&mut session, command);
+    cli::clear_answers();
+    // reading commands leave the files (and months never saved) as they are, while `tui`
+    // saves whatever was changed in it
+    let changes = changing || session.data.fingerprint() != before;
+    if cli::take_failure()
+        || (changes && !session.closed && !shutdown(&session.data, &session.root))
+    {
+        1
+    } else {
+        0
+    }
+}
+
+/// run the prompt commands in a script file, or stdin for `-` or no file
+///     - lines after a command answer its prompts, blank lines and `#` comments are skipped
+///     - stops at the first failing command without saving, unless `--continue` is given
+fn run_batch(invocation: cli::Invocation) -> i32 {
+    let keep_going = invocation.fields.iter().any(|(key, _)| key == "continue");
+    if let Some((key, _)) = invocation.fields.iter().find(|(key, _)| key != "continue") {
+        eprintln!("Unknown option --{}", key);
+        return 2;
+    }
+    let lines: Box<dyn BufRead> = match invocation.args.first().map(String::as_str) {
+        None | Some("-") => Box::new(BufReader::new(io::stdin())),
+        Some(path) => match fs::File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(e) => {
+                eprintln!("Error reading {}: {}", path, e);
+                return 2;
+            }
+        },
+    };
+    let root = match month_root(&invocation.globals) {
+        Ok(root) => root,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 2;
+        }
+    };
+    let mut session = Session::open(root);
+    let mut failed = false;
+    cli::take_failure();
+    cli::start_batch(lines);
+    loop {
+        let command = cli::prompt();
+        let line = cli::batch_line();
+        let flow = execute(&mut session, command);
+        cli::clear_answers();
+        if cli::take_failure() {
+            eprintln!("Failed at line {}", line);
+            if !keep_going {
+                return 1;
+            }
+            failed = true;
+        }
+        match flow {
+            Flow::Continue => (),
+            Flow::Save => break,
+            Flow::Discard => return i32::from(failed),
+        }
+    }
+    if (!session.closed && !shutdown(&session.data, &session.root)) || failed {
+        1
+    } else {
+        0
+    }
+}
+
+/// whether the month in root was closed with `--close`
+fn is_closed(root: &str) -> bool {
+    Path::new(root).join(CLOSED_MARKER).exists()
+}
+
+/// the directory holding every year/month budget
+fn budget_root() -> String {
     let home = match home::home_dir() {
         Some(path) => path,
         None => panic!("Could not get home directory path"),
@@ -68,7 +422,13 @@ fn get_dir_path() -> String {
         None => panic!("Could not convert home path to string"),
     };
     let mut root = String::from(home_str);
-    root.push_str("/budget_tracker/");
+    root.push_str("/budget_tracker");
+    root
+}
+
+fn get_dir_path() -> String {
+    let mut root = budget_root();
+    root.push('/');
     root.push_str(&cli::get_input("Year"));
     root.push('/');
     root.push_str(&cli::get_input("Month"));
@@ -76,9 +436,101 @@ fn get_dir_path() -> String {
     root
 }
 
+/// the directory of the month named by `--year` and `--month`, the current month by default
+///     - an existing directory for the month is used however it is named ("10" or "October")
+fn month_root(globals: &HashMap<String, String>) -> Result<String, String> {
+    for flag in globals.keys() {
+        if flag != "year" && flag != "month" {
+            return Err(format!("Unknown option --{}", flag));
+        }
+    }
+    let today = Local::now();
+    let year = match globals.get("year") {
+        Some(year) => year
+            .parse::<i32>()
+            .map_err(|_| format!("Not a year: {}", year))?,
+        None => today.year(),
+    };
+    let month = match globals.get("month") {
+        Some(month) => month_number(month).ok_or(format!("Not a month: {}", month))?,
+        None => today.month(),
+    };
+    let year_dir = format!("{}/{}", budget_root(), year);
+    if let Ok(month_dirs) = fs::read_dir(&year_dir) {
+        for month_dir in month_dirs.flatten() {
+            if month_number(&month_dir.file_name().to_string_lossy()) == Some(month) {
+                return Ok(month_dir.path().to_string_lossy().to_string());
+            }
+        }
+    }
+    Ok(format!("{}/{}", year_dir, month))
+}
+
+/// load every saved month from `from` to `to` (inclusive), oldest first
+///     - month directories may be named by number ("10") or by name ("October")
+///     - the open month is taken from memory so unsaved changes are included
+pub(crate) fn load_range(
+    from: (i32, u32),
+    to: (i32, u32),
+    open_root: &str,
+    open: &Data,
+) -> Vec<((i32, u32), Data)> {
+    let mut months: Vec<((i32, u32), PathBuf)> = Vec::new();
+    if let Ok(years) = fs::read_dir(budget_root()) {
+        for year_dir in years.flatten() {
+            let year = match year_dir.file_name().to_string_lossy().parse::<i32>() {
+                Ok(year) => year,
+                Err(_) => continue,
+            };
+            if let Ok(month_dirs) = fs::read_dir(year_dir.path()) {
+                for month_dir in month_dirs.flatten() {
+                    if let Some(month) = month_number(&month_dir.file_name().to_string_lossy()) {
+                        if (year, month) >= from && (year, month) <= to {
+                            months.push(((year, month), month_dir.path()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+    months.sort();
+    let mut range = Vec::new();
+    for (year_month, path) in months {
+        if path == Path::new(open_root) {
+            range.push((year_month, open.clone()));
+        } else if let Ok(files) = fs::read_dir(&path) {
+            let mut month = Data::new();
+            range.push((year_month, parse_dir(&mut month, files)));
+        }
+    }
+    range
+}
+
+/// the (year, month) of a `.../<year>/<month>` budget directory
+pub(crate) fn month_of(root: &str) -> Option<(i32, u32)> {
+    let path = Path::new(root);
+    let month = month_number(&path.file_name()?.to_string_lossy())?;
+    let year = path.parent()?.file_name()?.to_string_lossy().parse().ok()?;
+    Some((year, month))
+}
+
+/// "10", "Oct" and "October" are all month 10
+fn month_number(name: &str) -> Option<u32> {
+    match name.parse::<u32>() {
+        Ok(month) if (1..=12).contains(&month) => Some(month),
+        Ok(_) => None,
+        Err(_) => name
+            .parse::<Month>()
+            .ok()
+            .map(|month| month.number_from_month()),
+    }
+}
+
 fn parse_dir(new_data: &mut Data, files: ReadDir) -> Data {
     for f in files {
         match f {
+            // markers like .closed are not tables
+            Ok(f) if f.file_name().to_string_lossy().starts_with('.') => (),
             Ok(f) => {
                 if let Ok(contents) = fs::read_to_string(f.path()) {
                     parse_file(new_data, contents, f.path());
@@ -101,6 +553,10 @@ fn parse_file(new_data: &mut Data, contents: String, filename: PathBuf) {
         new_data.build_categories(contents);
     } else if filename.ends_with("Transaction.cls") {
         new_data.build_transactions(contents);
+    } else if filename.ends_with("Rule.cls") {
+        new_data.build_rules(contents);
+    } else if filename.ends_with("Payee.cls") {
+        new_data.build_payees(contents);
     } else {
         eprintln!("Unexpected filename while parsing file");
     }
@@ -1,3 +1,8 @@
 fn main() {
-    budgeters::run();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.is_empty() {
+        budgeters::run();
+    } else {
+        std::process::exit(budgeters::run_command(&args));
+    }
 }
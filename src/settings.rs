@@ -0,0 +1,384 @@
+use crate::budget_root;
+use std::cell::RefCell;
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+
+/// the settings file, in the budget root
+pub const SETTINGS_FILE: &str = "settings";
+
+thread_local! {
+    static SETTINGS: RefCell<Option<Settings>> = const { RefCell::new(None) };
+}
+
+/// what a value in a table means for the budget, which decides its color
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Tone {
+    /// an <Account> value at or above `threshold.account`, or any other positive number
+    Positive,
+    Negative,
+    /// a Transaction bringing money in
+    Income,
+    Expense,
+    /// a Category actual within its expected amount
+    Within,
+    /// a Category actual past `threshold.near` percent of its expected amount
+    Near,
+    /// a Category actual spent past its expected amount by more than `threshold.over`
+    Over,
+    /// an `<empty>` account or category
+    Empty,
+}
+
+impl Tone {
+    const ALL: [Tone; 8] = [
+        Tone::Positive,
+        Tone::Negative,
+        Tone::Income,
+        Tone::Expense,
+        Tone::Within,
+        Tone::Near,
+        Tone::Over,
+        Tone::Empty,
+    ];
+
+    /// the settings key of the tone's color
+    fn key(&self) -> &'static str {
+        match self {
+            Tone::Positive => "color.positive",
+            Tone::Negative => "color.negative",
+            Tone::Income => "color.income",
+            Tone::Expense => "color.expense",
+            Tone::Within => "color.within",
+            Tone::Near => "color.near",
+            Tone::Over => "color.over",
+            Tone::Empty => "color.empty",
+        }
+    }
+
+    fn default_color(&self) -> &'static str {
+        match self {
+            Tone::Positive | Tone::Income | Tone::Within => "green",
+            Tone::Negative | Tone::Expense | Tone::Over => "red",
+            Tone::Near => "yellow",
+            Tone::Empty => "blue",
+        }
+    }
+}
+
+/// the colors a setting can name, in terminal order
+const COLORS: [&str; 8] = [
+    "black", "red", "green", "yellow", "blue", "magenta", "cyan", "white",
+];
+
+/// the types whose columns `cols` can choose, as `columns.<type>` keys
+pub const TABLE_TYPES: [&str; 5] = ["acc", "cat", "tra", "rul", "pay"];
+
+/// settings with a fixed set of values, the first is the default
+const CHOICES: [(&str, &[&str]); 2] = [
+    ("table.pages", &["on", "off"]),
+    ("table.cells", &["truncate", "wrap", "full"]),
+];
+
+/// how table cells too wide for the terminal are shown
+#[derive(Clone, Copy, PartialEq)]
+pub enum CellFit {
+    /// cut short, ending in `…`
+    Truncate,
+    /// broken over several lines
+    Wrap,
+    /// left as they are
+    Full,
+}
+
+/// the thresholds, with their defaults
+const THRESHOLDS: [(&str, f32); 3] = [
+    ("threshold.account", 0.0),
+    ("threshold.near", 90.0),
+    ("threshold.over", 0.0),
+];
+
+/// `key = value` lines read from the settings file
+///     - keys missing from the file use their defaults
+pub struct Settings {
+    values: Vec<(String, String)>,
+}
+
+impl Settings {
+    /// read the settings file, warning about lines it can't use
+    ///     - `#` starts a comment, on its own line or after a value
+    fn load() -> Settings {
+        let path = format!("{}/{}", budget_root(), SETTINGS_FILE);
+        let contents = fs::read_to_string(&path).unwrap_or_default();
+        let mut values = Vec::new();
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.split_once('#').map_or(line, |(line, _)| line).trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim().to_string(), value.trim().to_string()),
+                None => {
+                    eprintln!("{} line {}: expected key = value", path, number + 1);
+                    continue;
+                }
+            };
+            if let Err(e) = Settings::check(&key, &value) {
+                eprintln!("{} line {}: {}", path, number + 1, e);
+                continue;
+            }
+            values.push((key, value));
+        }
+        Settings { values }
+    }
+
+    /// whether value can be given to key
+    fn check(key: &str, value: &str) -> Result<(), String> {
+        if Tone::ALL.iter().any(|tone| tone.key() == key) {
+            match ansi_code(value) {
+                Some(_) => Ok(()),
+                None => Err(format!(
+                    "{} is not a color, expected none or [bright-]{}",
+                    value,
+                    COLORS.join("|")
+                )),
+            }
+        } else if THRESHOLDS.iter().any(|(name, _)| *name == key) {
+            match value.parse::<f32>() {
+                Ok(_) => Ok(()),
+                Err(_) => Err(format!("{} for {} is not a number", value, key)),
+            }
+        } else if let Some((_, values)) = CHOICES.iter().find(|(name, _)| *name == key) {
+            if values.contains(&value) {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{} for {} should be one of: {}",
+                    value,
+                    key,
+                    values.join(", ")
+                ))
+            }
+        } else if key
+            .strip_prefix("columns.")
+            .is_some_and(|kind| TABLE_TYPES.contains(&kind))
+        {
+            Ok(())
+        } else {
+            Err(format!("Unknown setting {}", key))
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.values
+            .iter()
+            .rev()
+            .find(|(name, _)| name == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn threshold(&self, key: &str) -> f32 {
+        let default = THRESHOLDS
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map_or(0.0, |(_, value)| *value);
+        self.get(key)
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    }
+
+    fn color(&self, tone: Tone) -> &str {
+        self.get(tone.key()).unwrap_or(tone.default_color())
+    }
+
+    fn choice(&self, key: &str) -> &str {
+        let default = CHOICES
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map_or("", |(_, values)| values[0]);
+        self.get(key).unwrap_or(default)
+    }
+}
+
+/// run f with the settings, reading them the first time
+fn with<T>(f: impl FnOnce(&Settings) -> T) -> T {
+    SETTINGS.with(|settings| {
+        let mut settings = settings.borrow_mut();
+        f(settings.get_or_insert_with(Settings::load))
+    })
+}
+
+/// change a setting (or go back to its default for None) and save the settings file
+///     - other lines of the file, comments included, are kept as they are
+pub fn set(key: &str, value: Option<&str>) -> Result<(), String> {
+    if let Some(value) = value {
+        Settings::check(key, value)?;
+    }
+    let path = format!("{}/{}", budget_root(), SETTINGS_FILE);
+    let contents = fs::read_to_string(&path).unwrap_or_default();
+    let mut lines: Vec<String> = contents
+        .lines()
+        .filter(|line| {
+            line.split_once('=')
+                .is_none_or(|(name, _)| name.trim() != key || line.trim().starts_with('#'))
+        })
+        .map(String::from)
+        .collect();
+    if let Some(value) = value {
+        lines.push(format!("{} = {}", key, value));
+    }
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    if let Err(e) = fs::create_dir_all(budget_root()).and_then(|_| fs::write(&path, contents)) {
+        return Err(format!("Failed to save {}: {}", path, e));
+    }
+    // read again the next time they are needed
+    SETTINGS.with(|settings| *settings.borrow_mut() = None);
+    Ok(())
+}
+
+/// the columns chosen for the <type> kind with `cols`, None when all are shown
+pub fn chosen_columns(kind: &str) -> Option<Vec<String>> {
+    with(|settings| {
+        settings
+            .get(&format!("columns.{}", kind))
+            .map(|columns| columns.split_whitespace().map(String::from).collect())
+    })
+}
+
+/// the indexes of headers to show for the <type> kind, in the order chosen
+///     - names are written with `_` for spaces, e.g. `this_month`
+///     - names that are not headers are skipped, and all columns show if none are left
+pub fn columns(kind: &str, headers: &[&str]) -> Vec<usize> {
+    let shown: Vec<usize> = chosen_columns(kind)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|name| {
+            headers
+                .iter()
+                .position(|header| header.replace(' ', "_") == *name)
+        })
+        .collect();
+    if shown.is_empty() {
+        (0..headers.len()).collect()
+    } else {
+        shown
+    }
+}
+
+/// whether long tables stop after each screenful
+pub fn paging() -> bool {
+    with(|settings| settings.choice("table.pages") == "on")
+}
+
+pub fn cell_fit() -> CellFit {
+    with(|settings| match settings.choice("table.cells") {
+        "wrap" => CellFit::Wrap,
+        "full" => CellFit::Full,
+        _ => CellFit::Truncate,
+    })
+}
+
+/// whether output to stdout may be colored
+///     - not when it is piped or NO_COLOR is set (to anything but an empty value)
+pub fn colors_enabled() -> bool {
+    let no_color = env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    !no_color && io::stdout().is_terminal()
+}
+
+/// the tone of an <Account> value
+pub fn account_tone(value: f32) -> Tone {
+    if value < with(|settings| settings.threshold("threshold.account")) {
+        Tone::Negative
+    } else {
+        Tone::Positive
+    }
+}
+
+/// the tone of a Transaction amount
+pub fn amount_tone(amount: f32) -> Tone {
+    if amount < 0.0 {
+        Tone::Expense
+    } else {
+        Tone::Income
+    }
+}
+
+/// the tone of a Category actual, compared with what was expected
+///     - spending categories (expected at or below 0) are over once the actual is more negative
+///       than expected by `threshold.over`, and near from `threshold.near` percent of expected
+///     - income categories are near until the actual reaches what was expected
+pub fn category_tone(expected: f32, actual: f32) -> Tone {
+    with(|settings| {
+        if expected > 0.0 {
+            return if actual >= expected {
+                Tone::Within
+            } else {
+                Tone::Near
+            };
+        }
+        let near = settings.threshold("threshold.near");
+        if actual < expected - settings.threshold("threshold.over") {
+            Tone::Over
+        } else if near > 0.0 && expected < 0.0 && actual <= expected * near / 100.0 {
+            Tone::Near
+        } else {
+            Tone::Within
+        }
+    })
+}
+
+/// the prettytable style spec coloring text in tone, None if it is not colored
+pub fn style_spec(tone: Tone) -> Option<String> {
+    if !colors_enabled() {
+        return None;
+    }
+    let color = with(|settings| settings.color(tone).to_string());
+    let (bright, name) = match color.strip_prefix("bright-") {
+        Some(name) => (true, name),
+        None => (false, color.as_str()),
+    };
+    let letter = match name {
+        "black" => 'd',
+        "red" => 'r',
+        "green" => 'g',
+        "yellow" => 'y',
+        "blue" => 'b',
+        "magenta" => 'm',
+        "cyan" => 'c',
+        "white" => 'w',
+        _ => return None,
+    };
+    let letter = if bright {
+        letter.to_ascii_uppercase()
+    } else {
+        letter
+    };
+    Some(format!("F{}", letter))
+}
+
+/// text wrapped in the terminal escape coloring it in tone, if colors are enabled
+pub fn paint(text: &str, tone: Tone) -> String {
+    if !colors_enabled() {
+        return text.to_string();
+    }
+    match with(|settings| ansi_code(settings.color(tone))) {
+        Some(Some(code)) => format!("\u{1b}[{}m{}\u{1b}[0m", code, text),
+        _ => text.to_string(),
+    }
+}
+
+/// the SGR foreground code of a color name, Some(None) for `none`
+fn ansi_code(color: &str) -> Option<Option<u8>> {
+    if color == "none" {
+        return Some(None);
+    }
+    let (base, name) = match color.strip_prefix("bright-") {
+        Some(name) => (90, name),
+        None => (30, color),
+    };
+    COLORS
+        .iter()
+        .position(|known| *known == name)
+        .map(|index| Some(base + index as u8))
+}
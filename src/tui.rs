@@ -0,0 +1,625 @@
+use crate::cli::{self, Content};
+use crate::data::filter::Filter;
+use crate::data::transaction::Transaction;
+use crate::settings;
+use crate::{execute, Session};
+use std::io::{self, IsTerminal, Read, Write};
+
+/// the tables of the full-screen view, with the type abbreviation commands use for them
+const TABS: [(&str, &str); 3] = [
+    ("Accounts", "acc"),
+    ("Categories", "cat"),
+    ("Transactions", "tra"),
+];
+
+const HELP: &str =
+    "Tab switch  ↑↓ move  ←→ column  Enter edit  / search  d delete  u undo  r redo  U update  q quit";
+
+/// a key press, decoded from the bytes the terminal sends
+enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Enter,
+    Tab,
+    BackTab,
+    Backspace,
+    Esc,
+    /// Ctrl-C, which raw mode hands over as a byte instead of a signal
+    Cancel,
+    Other,
+}
+
+/// what typed keys go to
+enum Mode {
+    Browse,
+    Search,
+    /// editing the field of the selected row, with the text typed so far
+    Edit(String, String),
+    /// asking whether to delete the selected row
+    Delete,
+}
+
+/// a table as shown: headers, then (id, cells) per row, then its totals
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<(String, Vec<Content>)>,
+    totals: String,
+}
+
+/// where the view is and what it shows
+struct Screen {
+    tab: usize,
+    row: usize,
+    column: usize,
+    top: usize,
+    queries: [String; 3],
+    mode: Mode,
+    message: String,
+}
+
+/// the terminal in raw mode on the alternate screen, put back when dropped
+struct Terminal {
+    original: libc::termios,
+}
+
+impl Terminal {
+    fn enter() -> io::Result<Terminal> {
+        let mut original: libc::termios = unsafe { std::mem::zeroed() };
+        // SAFETY: tcgetattr and tcsetattr only read and write the termios passed in
+        unsafe {
+            if libc::tcgetattr(libc::STDIN_FILENO, &mut original) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut raw = original;
+            libc::cfmakeraw(&mut raw);
+            if libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &raw) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        print!("\x1b[?1049h\x1b[?25l");
+        io::stdout().flush()?;
+        Ok(Terminal { original })
+    }
+
+    /// (rows, columns) of the terminal, 24x80 if it can't tell
+    fn size() -> (usize, usize) {
+        cli::terminal_size().unwrap_or((24, 80))
+    }
+
+    fn read_key() -> Option<Key> {
+        let mut stdin = io::stdin();
+        let mut byte = [0u8; 1];
+        stdin.read_exact(&mut byte).ok()?;
+        let key = match byte[0] {
+            b'\r' | b'\n' => Key::Enter,
+            b'\t' => Key::Tab,
+            127 | 8 => Key::Backspace,
+            3 => Key::Cancel,
+            27 => {
+                if !Terminal::pending() {
+                    return Some(Key::Esc);
+                }
+                let mut sequence = [0u8; 2];
+                stdin.read_exact(&mut sequence).ok()?;
+                match &sequence {
+                    b"[A" | b"OA" => Key::Up,
+                    b"[B" | b"OB" => Key::Down,
+                    b"[C" | b"OC" => Key::Right,
+                    b"[D" | b"OD" => Key::Left,
+                    b"[H" | b"OH" => Key::Home,
+                    b"[F" | b"OF" => Key::End,
+                    b"[Z" => Key::BackTab,
+                    [b'[', digit] => {
+                        // `ESC [ 5 ~` and friends end with a tilde
+                        let mut tilde = [0u8; 1];
+                        stdin.read_exact(&mut tilde).ok()?;
+                        match digit {
+                            b'1' | b'7' => Key::Home,
+                            b'4' | b'8' => Key::End,
+                            b'5' => Key::PageUp,
+                            b'6' => Key::PageDown,
+                            _ => Key::Other,
+                        }
+                    }
+                    _ => Key::Other,
+                }
+            }
+            first if first < 32 => Key::Other,
+            first => {
+                // the rest of a UTF-8 character
+                let length = match first {
+                    0xC0..=0xDF => 2,
+                    0xE0..=0xEF => 3,
+                    0xF0..=0xF7 => 4,
+                    _ => 1,
+                };
+                let mut bytes = vec![first; length];
+                stdin.read_exact(&mut bytes[1..]).ok()?;
+                match String::from_utf8(bytes).ok()?.chars().next() {
+                    Some(c) => Key::Char(c),
+                    None => Key::Other,
+                }
+            }
+        };
+        Some(key)
+    }
+
+    /// whether more bytes follow shortly, telling Esc apart from an escape sequence
+    fn pending() -> bool {
+        let mut poll = libc::pollfd {
+            fd: libc::STDIN_FILENO,
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: poll only touches the one pollfd passed in
+        unsafe { libc::poll(&mut poll, 1, 30) > 0 }
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        // SAFETY: restores the settings read in `enter`
+        unsafe {
+            libc::tcsetattr(libc::STDIN_FILENO, libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// show the open month full-screen until `q`
+///     - changes run as the same commands the prompt would, so they can be undone there too
+pub fn run(session: &mut Session) {
+    if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+        cli::fail("The full-screen view needs a terminal");
+        return;
+    }
+    let terminal = match Terminal::enter() {
+        Ok(terminal) => terminal,
+        Err(e) => {
+            cli::fail(&format!("Could not start the full-screen view: {}", e));
+            return;
+        }
+    };
+    cli::capture_notes(true);
+    let mut screen = Screen {
+        tab: 2,
+        row: 0,
+        column: 1,
+        top: 0,
+        queries: Default::default(),
+        mode: Mode::Browse,
+        message: String::new(),
+    };
+    loop {
+        let table = screen.table(session);
+        screen.draw(session, &table);
+        let key = match Terminal::read_key() {
+            Some(key) => key,
+            None => break,
+        };
+        if !screen.handle(session, &table, key) {
+            break;
+        }
+    }
+    cli::capture_notes(false);
+    drop(terminal);
+}
+
+impl Screen {
+    /// the rows of the open tab, narrowed by its search
+    fn table(&mut self, session: &Session) -> Table {
+        let data = &session.data;
+        let query = self.queries[self.tab].to_lowercase();
+        let table = match self.tab {
+            0 => {
+                let accounts: Vec<_> = session
+                    .view
+                    .accounts(data)
+                    .into_iter()
+                    .filter(|acc| acc.get_name().to_lowercase().contains(&query))
+                    .collect();
+                let worth: f32 = accounts.iter().fold(0.0, |sum, acc| sum + acc.get_value());
+                Table {
+                    headers: vec!["id".into(), "name".into(), "value".into()],
+                    totals: format!(
+                        "{} accounts, worth ${}",
+                        accounts.len(),
+                        cli::money_round(worth)
+                    ),
+                    rows: accounts
+                        .iter()
+                        .map(|acc| {
+                            let id = acc.get_simple_id();
+                            let cells = vec![
+                                Content::St(id.clone()),
+                                Content::St(acc.get_name().into()),
+                                Content::Toned(
+                                    acc.get_value().to_string(),
+                                    settings::account_tone(*acc.get_value()),
+                                ),
+                            ];
+                            (id, cells)
+                        })
+                        .collect(),
+                }
+            }
+            1 => {
+                let categories: Vec<_> = session
+                    .view
+                    .categories(data)
+                    .into_iter()
+                    .filter(|cat| cat.get_name().to_lowercase().contains(&query))
+                    .collect();
+                let budgeted = categories.iter().filter(|cat| cat.get_name() != "Rollover");
+                let expected: f32 = budgeted
+                    .clone()
+                    .fold(0.0, |sum, cat| sum + cat.get_expected());
+                let actual: f32 = budgeted.fold(0.0, |sum, cat| sum + cat.get_actual());
+                Table {
+                    headers: vec![
+                        "id".into(),
+                        "name".into(),
+                        "expected".into(),
+                        "actual".into(),
+                    ],
+                    totals: format!(
+                        "{} categories, planned ${}, actual ${}",
+                        categories.len(),
+                        cli::money_round(expected),
+                        cli::money_round(actual)
+                    ),
+                    rows: categories
+                        .iter()
+                        .map(|cat| {
+                            let id = cat.get_simple_id();
+                            let cells = vec![
+                                Content::St(id.clone()),
+                                Content::St(cat.get_name().into()),
+                                Content::Num(cat.get_expected().to_string()),
+                                Content::Toned(
+                                    cat.get_actual().to_string(),
+                                    settings::category_tone(*cat.get_expected(), *cat.get_actual()),
+                                ),
+                            ];
+                            (id, cells)
+                        })
+                        .collect(),
+                }
+            }
+            _ => {
+                let filter = match Filter::parse(&self.queries[self.tab]) {
+                    Ok(filter) => filter,
+                    Err(e) => {
+                        self.message = e;
+                        Filter::All
+                    }
+                };
+                let transactions: Vec<&Transaction> = session
+                    .view
+                    .transactions(data)
+                    .into_iter()
+                    .filter(|tra| filter.matches(tra))
+                    .collect();
+                let total: f32 = transactions
+                    .iter()
+                    .fold(0.0, |sum, tra| sum + tra.get_amount());
+                Table {
+                    headers: Transaction::HEADERS.iter().map(|h| h.to_string()).collect(),
+                    totals: format!(
+                        "{} transactions, total ${}",
+                        transactions.len(),
+                        cli::money_round(total)
+                    ),
+                    rows: transactions
+                        .iter()
+                        .map(|tra| (tra.get_simple_id(), tra.to_row()))
+                        .collect(),
+                }
+            }
+        };
+        // only the columns chosen with `cols`
+        let shown = settings::columns(
+            TABS[self.tab].1,
+            &table
+                .headers
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<&str>>(),
+        );
+        Table {
+            headers: shown.iter().map(|&c| table.headers[c].clone()).collect(),
+            rows: table
+                .rows
+                .into_iter()
+                .map(|(id, cells)| (id, shown.iter().map(|&c| cells[c].clone()).collect()))
+                .collect(),
+            totals: table.totals,
+        }
+    }
+
+    /// the columns that can be edited: all but the id, and a category's actual
+    fn editable(&self, table: &Table) -> Vec<usize> {
+        (0..table.headers.len())
+            .filter(|&column| !matches!(table.headers[column].as_str(), "id" | "actual"))
+            .collect()
+    }
+
+    /// act on a key, returns false to leave the view
+    fn handle(&mut self, session: &mut Session, table: &Table, key: Key) -> bool {
+        let mode = std::mem::replace(&mut self.mode, Mode::Browse);
+        match mode {
+            Mode::Search => {
+                let query = &mut self.queries[self.tab];
+                match key {
+                    Key::Enter => (),
+                    Key::Esc | Key::Cancel => query.clear(),
+                    Key::Backspace => {
+                        query.pop();
+                        self.mode = Mode::Search;
+                    }
+                    Key::Char(c) => {
+                        query.push(c);
+                        self.row = 0;
+                        self.mode = Mode::Search;
+                    }
+                    _ => self.mode = Mode::Search,
+                }
+            }
+            Mode::Edit(field, mut text) => match key {
+                Key::Enter => {
+                    if let Some((id, _)) = table.rows.get(self.row) {
+                        let words = vec![
+                            TABS[self.tab].1.to_string(),
+                            id.clone(),
+                            format!("{}={}", field, text),
+                        ];
+                        self.message = format!("Set {} of {} to {}", field, id, text);
+                        self.run(session, "e", words);
+                    }
+                }
+                Key::Esc | Key::Cancel => (),
+                Key::Backspace => {
+                    text.pop();
+                    self.mode = Mode::Edit(field, text);
+                }
+                Key::Char(c) => {
+                    text.push(c);
+                    self.mode = Mode::Edit(field, text);
+                }
+                _ => self.mode = Mode::Edit(field, text),
+            },
+            Mode::Delete => {
+                if let (Key::Char('y'), Some((id, _))) = (key, table.rows.get(self.row)) {
+                    let words = vec![TABS[self.tab].1.to_string(), id.clone()];
+                    self.message = format!("Deleted {}", id);
+                    self.run(session, "d", words);
+                }
+            }
+            Mode::Browse => {
+                let editable = self.editable(table);
+                let page = Terminal::size().0.saturating_sub(5).max(1);
+                let last = table.rows.len().saturating_sub(1);
+                match key {
+                    Key::Esc if !self.queries[self.tab].is_empty() => {
+                        self.queries[self.tab].clear()
+                    }
+                    Key::Char('q') | Key::Esc | Key::Cancel => return false,
+                    Key::Tab => self.switch(self.tab + 1),
+                    Key::BackTab => self.switch(self.tab + TABS.len() - 1),
+                    Key::Char(c @ '1'..='3') => self.switch(c as usize - '1' as usize),
+                    Key::Up | Key::Char('k') => self.row = self.row.saturating_sub(1),
+                    Key::Down | Key::Char('j') => self.row = (self.row + 1).min(last),
+                    Key::PageUp => self.row = self.row.saturating_sub(page),
+                    Key::PageDown => self.row = (self.row + page).min(last),
+                    Key::Home | Key::Char('g') => self.row = 0,
+                    Key::End | Key::Char('G') => self.row = last,
+                    Key::Left | Key::Char('h') => {
+                        if let Some(&column) = editable.iter().rev().find(|&&c| c < self.column) {
+                            self.column = column;
+                        }
+                    }
+                    Key::Right | Key::Char('l') => {
+                        if let Some(&column) = editable.iter().find(|&&c| c > self.column) {
+                            self.column = column;
+                        }
+                    }
+                    Key::Char('/') => self.mode = Mode::Search,
+                    Key::Enter | Key::Char('e') => {
+                        if let Some((_, cells)) = table.rows.get(self.row) {
+                            let field = table.headers[self.column].clone();
+                            self.mode = Mode::Edit(field, cells[self.column].text().to_string());
+                        }
+                    }
+                    Key::Char('d') if !table.rows.is_empty() => self.mode = Mode::Delete,
+                    Key::Char('u') => {
+                        self.message = String::from("Undid");
+                        self.run(session, "u", Vec::new());
+                    }
+                    Key::Char('r') => {
+                        self.message = String::from("Redid");
+                        self.run(session, "r", Vec::new());
+                    }
+                    Key::Char('U') => {
+                        self.message = String::from("Updated account values and category actuals");
+                        self.run(session, "--update", Vec::new());
+                    }
+                    _ => (),
+                }
+            }
+        }
+        true
+    }
+
+    fn switch(&mut self, tab: usize) {
+        self.tab = tab % TABS.len();
+        self.row = 0;
+        self.top = 0;
+        self.column = 1;
+    }
+
+    /// run a prompt command with its answers given, keeping the message set for success
+    ///     - a failure shows what went wrong instead, an undo or redo says what it did
+    fn run(&mut self, session: &mut Session, command: &str, words: Vec<String>) {
+        cli::take_failure();
+        cli::take_notes();
+        cli::set_strict(true);
+        execute(session, cli::to_command(command, words));
+        cli::clear_answers();
+        let notes = cli::take_notes();
+        if cli::take_failure() || matches!(command, "u" | "r") {
+            if let Some(last) = notes.last() {
+                self.message = last.clone();
+            }
+        } else if let Some(new) = notes.iter().find(|note| note.starts_with("New ")) {
+            self.message = format!("{} ({})", self.message, new);
+        }
+    }
+
+    fn draw(&mut self, session: &Session, table: &Table) {
+        let (height, width) = Terminal::size();
+        let mut frame = String::from("\x1b[H\x1b[2J");
+        // tabs, and the month on the right
+        let mut tabs = String::new();
+        for (index, (name, _)) in TABS.iter().enumerate() {
+            if index == self.tab {
+                tabs.push_str(&format!("\x1b[7m {} \x1b[0m ", name));
+            } else {
+                tabs.push_str(&format!(" {}  ", name));
+            }
+        }
+        let month = if session.closed {
+            format!("{} (closed)", session.root)
+        } else {
+            session.root.clone()
+        };
+        frame.push_str(&tabs);
+        let used = TABS
+            .iter()
+            .map(|(name, _)| name.chars().count() + 4)
+            .sum::<usize>();
+        if used + month.chars().count() < width {
+            frame.push_str(&" ".repeat(width - used - month.chars().count()));
+            frame.push_str(&month);
+        }
+        frame.push_str("\r\n");
+        // the search box
+        let query = &self.queries[self.tab];
+        let search = match self.mode {
+            Mode::Search => format!("/ {}\x1b[7m \x1b[0m", query),
+            _ if query.is_empty() => String::from("\x1b[2m/ to search\x1b[0m"),
+            _ => format!("/ {}", query),
+        };
+        frame.push_str(&search);
+        frame.push_str("\r\n");
+        // the table, scrolled so the selected row shows
+        let rows = height.saturating_sub(4).max(1);
+        self.row = self.row.min(table.rows.len().saturating_sub(1));
+        let editable = self.editable(table);
+        if !editable.contains(&self.column) {
+            self.column = editable.first().copied().unwrap_or(0);
+        }
+        if self.row < self.top {
+            self.top = self.row;
+        } else if self.row >= self.top + rows {
+            self.top = self.row + 1 - rows;
+        }
+        let widths = fit(table, width);
+        frame.push_str(if settings::colors_enabled() {
+            "\x1b[1;33m"
+        } else {
+            "\x1b[1m"
+        });
+        frame.push_str(&line(&table.headers, &widths));
+        frame.push_str("\x1b[0m\r\n");
+        for index in self.top..(self.top + rows - 1).min(table.rows.len()) {
+            let cells = &table.rows[index].1;
+            if index == self.row {
+                for (column, cell) in cells.iter().enumerate() {
+                    let text = pad(cell.text(), widths[column]);
+                    if column == self.column {
+                        frame.push_str(&format!("\x1b[7;4m{}\x1b[0m ", text));
+                    } else {
+                        frame.push_str(&format!("\x1b[7m{}\x1b[0m ", text));
+                    }
+                }
+            } else {
+                let painted: Vec<String> = cells
+                    .iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| match cell.tone() {
+                        Some(tone) => settings::paint(&pad(cell.text(), *width), tone),
+                        None => pad(cell.text(), *width),
+                    })
+                    .collect();
+                frame.push_str(&painted.join(" "));
+            }
+            frame.push_str("\r\n");
+        }
+        // the status bar (or the line being edited) at the bottom
+        let status = match &self.mode {
+            Mode::Edit(field, text) => format!("{}: {}\x1b[7m \x1b[0m", field, text),
+            Mode::Delete => String::from("Delete the selected row? (y/n)"),
+            _ => {
+                let mut status = table.totals.clone();
+                if !self.message.is_empty() {
+                    status.push_str(" | ");
+                    status.push_str(&self.message);
+                }
+                status.push_str(" | ");
+                status.push_str(HELP);
+                format!("\x1b[7m{}\x1b[0m", pad(&status, width))
+            }
+        };
+        frame.push_str(&format!("\x1b[{};1H{}", height, status));
+        print!("{}", frame);
+        let _ = io::stdout().flush();
+    }
+}
+
+/// column widths that fit the table in width, shrinking the widest columns first
+fn fit(table: &Table, width: usize) -> Vec<usize> {
+    let mut widths: Vec<usize> = table.headers.iter().map(|h| h.chars().count()).collect();
+    for (_, cells) in &table.rows {
+        for (column, cell) in cells.iter().enumerate() {
+            widths[column] = widths[column].max(cell.text().chars().count());
+        }
+    }
+    // one space between columns
+    while widths.iter().sum::<usize>() + widths.len() > width {
+        let widest = (0..widths.len()).max_by_key(|&c| widths[c]).unwrap_or(0);
+        if widths[widest] <= 4 {
+            break;
+        }
+        widths[widest] -= 1;
+    }
+    widths
+}
+
+fn line(cells: &[String], widths: &[usize]) -> String {
+    cells
+        .iter()
+        .zip(widths)
+        .map(|(cell, width)| pad(cell, *width))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+/// text cut or padded to exactly width characters, cut text ends in `…`
+fn pad(text: &str, width: usize) -> String {
+    let length = text.chars().count();
+    if length > width {
+        let mut cut: String = text.chars().take(width.saturating_sub(1)).collect();
+        cut.push('…');
+        cut
+    } else {
+        format!("{}{}", text, " ".repeat(width - length))
+    }
+}
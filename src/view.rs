@@ -0,0 +1,258 @@
+use crate::cli;
+use crate::data::{
+    account::Account, category::Category, payee::Payee, rule::Rule, transaction::Transaction, Data,
+};
+use crate::settings;
+use std::cmp::Ordering;
+
+/// a value rows can be sorted by
+pub enum SortValue {
+    Num(f64),
+    Text(String),
+}
+
+/// a field to sort by, and which way
+#[derive(Clone)]
+struct SortKey {
+    field: String,
+    descending: bool,
+}
+
+/// how tables are shown, kept for the rest of the session
+pub struct View {
+    accounts: Vec<SortKey>,
+    categories: Vec<SortKey>,
+    transactions: Vec<SortKey>,
+}
+
+impl View {
+    pub fn new() -> View {
+        View {
+            accounts: Vec::new(),
+            categories: Vec::new(),
+            transactions: Vec::new(),
+        }
+    }
+
+    /// set the order of a table, e.g. `s tra category date desc` or `s cat -actual`
+    ///     - the type defaults to tra, later fields break ties in earlier ones
+    ///     - `desc` after a field (or `-` before it) sorts it descending
+    ///     - a type without fields goes back to file order, no arguments shows the sorts
+    pub fn sort(&mut self, arg: &str) {
+        let mut words: Vec<&str> = arg.split_whitespace().collect();
+        if words.is_empty() {
+            for (name, keys) in [
+                ("acc", &self.accounts),
+                ("cat", &self.categories),
+                ("tra", &self.transactions),
+            ] {
+                note!("{}: {}", name, describe(keys));
+            }
+            return;
+        }
+        let (name, fields): (&str, &[&str]) = match words[0] {
+            "acc" => ("acc", &Account::SORT_FIELDS),
+            "cat" => ("cat", &Category::SORT_FIELDS),
+            "tra" => ("tra", &Transaction::SORT_FIELDS),
+            _ => {
+                words.insert(0, "tra");
+                ("tra", &Transaction::SORT_FIELDS)
+            }
+        };
+        let mut keys: Vec<SortKey> = Vec::new();
+        for word in &words[1..] {
+            match *word {
+                "asc" | "desc" => match keys.last_mut() {
+                    Some(key) => key.descending = *word == "desc",
+                    None => {
+                        cli::fail(&format!("Expected a field before {}", word));
+                        return;
+                    }
+                },
+                _ => {
+                    let field = word.trim_start_matches('-');
+                    if !fields.contains(&field) {
+                        cli::fail(&format!(
+                            "Can't sort {} by {}, expected one of: {}",
+                            name,
+                            field,
+                            fields.join(", ")
+                        ));
+                        return;
+                    }
+                    keys.push(SortKey {
+                        field: field.to_string(),
+                        descending: word.starts_with('-'),
+                    });
+                }
+            }
+        }
+        note!("Sorting {} by {}", name, describe(&keys));
+        match name {
+            "acc" => self.accounts = keys,
+            "cat" => self.categories = keys,
+            _ => self.transactions = keys,
+        }
+    }
+
+    pub fn accounts<'a>(&self, data: &'a Data) -> Vec<&'a Account> {
+        ordered(&data.accounts, &self.accounts, Account::sort_value)
+    }
+
+    pub fn categories<'a>(&self, data: &'a Data) -> Vec<&'a Category> {
+        ordered(&data.categories, &self.categories, Category::sort_value)
+    }
+
+    pub fn transactions<'a>(&self, data: &'a Data) -> Vec<&'a Transaction> {
+        ordered(
+            &data.transactions,
+            &self.transactions,
+            Transaction::sort_value,
+        )
+    }
+}
+
+/// choose the columns a table shows, e.g. `cols tra -id` or `cols acc name value`, saved in settings
+///     - names alone give the columns in that order, `-name` hides and `+name` shows one more
+///     - `all` shows every column again, a type alone shows its choice and no arguments shows all
+pub fn columns(arg: &str) {
+    let words: Vec<&str> = arg.split_whitespace().collect();
+    if words.is_empty() {
+        for kind in settings::TABLE_TYPES {
+            note!("{}: {}", kind, shown(kind).join(" "));
+        }
+        return;
+    }
+    let kind = words[0];
+    let headers = match table_headers(kind) {
+        Some(headers) => headers,
+        None => {
+            cli::fail(&format!(
+                "Can't choose columns of {}, expected one of: {}",
+                kind,
+                settings::TABLE_TYPES.join(", ")
+            ));
+            return;
+        }
+    };
+    if words.len() == 1 {
+        note!("{}: {}", kind, shown(kind).join(" "));
+        note!("Columns: {}", headers.join(", "));
+        return;
+    }
+    let key = format!("columns.{}", kind);
+    if words[1..] == ["all"] {
+        if let Err(e) = settings::set(&key, None) {
+            cli::fail(&e);
+            return;
+        }
+        note!("{}: {}", kind, headers.join(" "));
+        return;
+    }
+    let mut chosen: Vec<String> = Vec::new();
+    for word in &words[1..] {
+        let name = word.trim_start_matches(['-', '+']);
+        if !headers.iter().any(|header| header == name) {
+            cli::fail(&format!(
+                "{} has no column {}, expected one of: {}",
+                kind,
+                name,
+                headers.join(", ")
+            ));
+            return;
+        }
+        // a change to the current choice starts from it
+        if chosen.is_empty() && (word.starts_with('-') || word.starts_with('+')) {
+            chosen = shown(kind);
+        }
+        chosen.retain(|column| column != name);
+        if !word.starts_with('-') {
+            chosen.push(name.to_string());
+        }
+    }
+    if chosen.is_empty() {
+        cli::fail(&format!("{} needs at least one column", kind));
+        return;
+    }
+    if let Err(e) = settings::set(&key, Some(&chosen.join(" "))) {
+        cli::fail(&e);
+        return;
+    }
+    note!("{}: {}", kind, chosen.join(" "));
+}
+
+/// the column names of the table `cols` names kind, with `_` for spaces, None for an unknown type
+fn table_headers(kind: &str) -> Option<Vec<String>> {
+    let headers = match kind {
+        "acc" => Account::HEADERS.to_vec(),
+        "cat" => Category::HEADERS.to_vec(),
+        "tra" => Transaction::HEADERS.to_vec(),
+        "rul" => Rule::HEADERS.to_vec(),
+        "pay" => {
+            let mut headers = Payee::HEADERS.to_vec();
+            headers.push("this month");
+            headers
+        }
+        _ => return None,
+    };
+    Some(headers.iter().map(|h| h.replace(' ', "_")).collect())
+}
+
+/// the columns the kind's table shows now
+fn shown(kind: &str) -> Vec<String> {
+    let headers = table_headers(kind).unwrap_or_default();
+    let names: Vec<&str> = headers.iter().map(String::as_str).collect();
+    settings::columns(kind, &names)
+        .into_iter()
+        .map(|column| headers[column].clone())
+        .collect()
+}
+
+fn describe(keys: &[SortKey]) -> String {
+    if keys.is_empty() {
+        return String::from("file order");
+    }
+    keys.iter()
+        .map(|key| {
+            if key.descending {
+                format!("{} desc", key.field)
+            } else {
+                key.field.clone()
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// rows in the order of keys, keeping file order for ties
+fn ordered<'a, T>(
+    rows: &'a [T],
+    keys: &[SortKey],
+    value: impl Fn(&T, &str) -> SortValue,
+) -> Vec<&'a T> {
+    let mut sorted: Vec<&T> = rows.iter().collect();
+    sorted.sort_by(|a, b| {
+        for key in keys {
+            let order = compare(&value(a, &key.field), &value(b, &key.field));
+            let order = if key.descending {
+                order.reverse()
+            } else {
+                order
+            };
+            if order != Ordering::Equal {
+                return order;
+            }
+        }
+        Ordering::Equal
+    });
+    sorted
+}
+
+fn compare(a: &SortValue, b: &SortValue) -> Ordering {
+    match (a, b) {
+        (SortValue::Num(a), SortValue::Num(b)) => a.partial_cmp(b).unwrap_or(Ordering::Equal),
+        (SortValue::Text(a), SortValue::Text(b)) => a.to_lowercase().cmp(&b.to_lowercase()),
+        (SortValue::Num(_), SortValue::Text(_)) => Ordering::Less,
+        (SortValue::Text(_), SortValue::Num(_)) => Ordering::Greater,
+    }
+}